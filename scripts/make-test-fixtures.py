@@ -0,0 +1,272 @@
+#!/usr/bin/env python3
+"""
+Voice-to-Text MCP - Test fixture generator
+
+Writes the compressed audio files used by the decoder tests to tests/fixtures.
+Each file is a one-second 48kHz mono tone. The MP3 and AAC files need no encoder:
+they are built directly from their codec's bitstream syntax, using a single
+quantized spectral line per frame. Opus frames are too involved to write by hand,
+so tone.opus is encoded with libopus (found on the library path, or named by the
+LIBOPUS environment variable) and skipped when it isn't available.
+
+    tone.mp3   MPEG-1 Layer III, 64 kbps, 42 frames of 1152 samples
+    tone.m4a   AAC-LC in an MP4 container, 47 frames of 1024 samples
+    tone.opus  Opus in Ogg, 64 kbps, 51 frames of 960 samples trimmed to one second
+"""
+
+import ctypes
+import ctypes.util
+import math
+import os
+import struct
+import sys
+
+SAMPLE_RATE = 48000
+FIXTURES_DIR = os.path.join(os.path.dirname(os.path.abspath(__file__)), "..", "tests", "fixtures")
+
+
+class BitWriter:
+    def __init__(self):
+        self.bits = []
+
+    def write(self, value, count):
+        for shift in range(count - 1, -1, -1):
+            self.bits.append((value >> shift) & 1)
+
+    def write_code(self, code):
+        """Write a Huffman code given as a string of 0s and 1s"""
+        self.bits.extend(int(bit) for bit in code)
+
+    def to_bytes(self):
+        padded = self.bits + [0] * (-len(self.bits) % 8)
+        return bytes(
+            int("".join(str(bit) for bit in padded[i:i + 8]), 2)
+            for i in range(0, len(padded), 8)
+        )
+
+
+# Sign of the tone's spectral line in consecutive frames; a line at the centre of
+# an MDCT bin advances a quarter turn per frame
+TONE_SIGNS = [0, 1, 1, 0]
+
+
+def mp3_frame(index):
+    """One mono MPEG-1 Layer III frame with spectral line 10 (~440Hz) set in both granules"""
+    frame_bytes = 144 * 64000 // SAMPLE_RATE
+    header = bytes([0xFF, 0xFB, 0x54, 0xC0])  # no CRC, 64 kbps, 48kHz, mono
+
+    # Main data per granule: count1 quadruples coded with table B (4-bit code 15 - value,
+    # value bits v w x y) up to the quadruple holding line 10, then its sign bit
+    main = BitWriter()
+    part3_lengths = []
+    for granule in range(2):
+        main.write(15, 4)              # lines 0-3 zero
+        main.write(15, 4)              # lines 4-7 zero
+        main.write(15 - 0b0010, 4)     # line 10 is x in lines 8-11
+        main.write(TONE_SIGNS[(2 * index + granule) % 4], 1)
+        part3_lengths.append(13)
+
+    side = BitWriter()
+    side.write(0, 9)   # main_data_begin: no bit reservoir
+    side.write(0, 5)   # private bits
+    side.write(0, 4)   # scfsi
+    for part3_length in part3_lengths:
+        side.write(part3_length, 12)   # part2_3_length (no scalefactor bits)
+        side.write(0, 9)               # big_values
+        side.write(200, 8)             # global_gain
+        side.write(0, 4)               # scalefac_compress
+        side.write(0, 1)               # window_switching_flag
+        side.write(0, 15)              # table_select x3
+        side.write(0, 4)               # region0_count
+        side.write(0, 3)               # region1_count
+        side.write(0, 1)               # preflag
+        side.write(0, 1)               # scalefac_scale
+        side.write(1, 1)               # count1table_select: table B
+
+    frame = header + side.to_bytes() + main.to_bytes()
+    return frame + bytes(frame_bytes - len(frame))
+
+
+def aac_frame(index):
+    """One raw AAC-LC frame: a single channel element with spectral line 18 (~430Hz)"""
+    bits = BitWriter()
+    bits.write(0, 3)      # ID_SCE
+    bits.write(0, 4)      # element_instance_tag
+    bits.write(188, 8)    # global_gain
+    # ics_info
+    bits.write(0, 1)      # ics_reserved_bit
+    bits.write(0, 2)      # ONLY_LONG_SEQUENCE
+    bits.write(0, 1)      # window_shape
+    bits.write(5, 6)      # max_sfb: bands 0-4
+    bits.write(0, 1)      # predictor_data_present
+    # section_data: bands 0-3 silent, band 4 (lines 16-19) in codebook 1
+    bits.write(0, 4)
+    bits.write(4, 5)
+    bits.write(1, 4)
+    bits.write(1, 5)
+    # scale_factor_data: band 4 at global_gain (delta 0 is code "0")
+    bits.write_code("0")
+    bits.write(0, 1)      # pulse_data_present
+    bits.write(0, 1)      # tns_data_present
+    bits.write(0, 1)      # gain_control_data_present
+    # spectral_data: codebook 1 quadruple (0, 0, +/-1, 0)
+    bits.write_code("10110" if TONE_SIGNS[index % 4] == 0 else "10101")
+    bits.write(7, 3)      # ID_END
+    return bits.to_bytes()
+
+
+def box(kind, *payload):
+    data = b"".join(payload)
+    return struct.pack(">I", 8 + len(data)) + kind + data
+
+
+def full_box(kind, version_flags, *payload):
+    return box(kind, struct.pack(">I", version_flags), *payload)
+
+
+def descriptor(tag, *payload):
+    data = b"".join(payload)
+    return bytes([tag, len(data)]) + data
+
+
+def m4a_file(frames):
+    # AudioSpecificConfig: AAC-LC (2), 48kHz (index 3), mono
+    audio_config = bytes([(2 << 3) | (3 >> 1), ((3 & 1) << 7) | (1 << 3)])
+    esds = full_box(b"esds", 0, descriptor(
+        0x03, struct.pack(">HB", 1, 0),
+        descriptor(0x04, bytes([0x40, 0x15]), bytes(3), struct.pack(">II", 64000, 64000),
+                   descriptor(0x05, audio_config)),
+        descriptor(0x06, bytes([0x02])),
+    ))
+    mp4a = box(b"mp4a", bytes(6), struct.pack(">H", 1), bytes(8),
+               struct.pack(">HHHHI", 1, 16, 0, 0, SAMPLE_RATE << 16), esds)
+
+    duration = 1024 * len(frames)
+    sizes = b"".join(struct.pack(">I", len(frame)) for frame in frames)
+    ftyp = box(b"ftyp", b"M4A ", struct.pack(">I", 0), b"M4A mp42isom")
+
+    def moov(chunk_offset):
+        matrix = struct.pack(">9I", 0x10000, 0, 0, 0, 0x10000, 0, 0, 0, 0x40000000)
+        stbl = box(b"stbl",
+                   full_box(b"stsd", 0, struct.pack(">I", 1), mp4a),
+                   full_box(b"stts", 0, struct.pack(">III", 1, len(frames), 1024)),
+                   full_box(b"stsc", 0, struct.pack(">IIII", 1, 1, len(frames), 1)),
+                   full_box(b"stsz", 0, struct.pack(">II", 0, len(frames)), sizes),
+                   full_box(b"stco", 0, struct.pack(">II", 1, chunk_offset)))
+        minf = box(b"minf",
+                   full_box(b"smhd", 0, bytes(4)),
+                   box(b"dinf", full_box(b"dref", 0, struct.pack(">I", 1), full_box(b"url ", 1))),
+                   stbl)
+        mdia = box(b"mdia",
+                   full_box(b"mdhd", 0, struct.pack(">IIIIHH", 0, 0, SAMPLE_RATE, duration, 0x55C4, 0)),
+                   full_box(b"hdlr", 0, bytes(4), b"soun", bytes(12), b"SoundHandler\x00"),
+                   minf)
+        tkhd = full_box(b"tkhd", 7, struct.pack(">IIIII", 0, 0, 1, 0, duration), bytes(8),
+                        struct.pack(">HHHH", 0, 0, 0x0100, 0), matrix, struct.pack(">II", 0, 0))
+        mvhd = full_box(b"mvhd", 0, struct.pack(">IIII", 0, 0, SAMPLE_RATE, duration),
+                        struct.pack(">IH", 0x10000, 0x0100), bytes(10), matrix, bytes(24),
+                        struct.pack(">I", 2))
+        return box(b"moov", mvhd, box(b"trak", tkhd, mdia))
+
+    # The sample data follows the 8-byte mdat header after ftyp and moov
+    chunk_offset = len(ftyp) + len(moov(0)) + 8
+    return ftyp + moov(chunk_offset) + box(b"mdat", *frames)
+
+
+OPUS_FRAME = 960  # 20ms
+OPUS_APPLICATION_AUDIO = 2049
+OPUS_SET_BITRATE_REQUEST = 4002
+OPUS_GET_LOOKAHEAD_REQUEST = 4027
+
+
+def load_libopus():
+    path = os.environ.get("LIBOPUS") or ctypes.util.find_library("opus")
+    if path is None:
+        return None
+    libopus = ctypes.CDLL(path)
+    libopus.opus_encoder_create.restype = ctypes.c_void_p
+    libopus.opus_encoder_create.argtypes = [ctypes.c_int32, ctypes.c_int, ctypes.c_int, ctypes.POINTER(ctypes.c_int)]
+    libopus.opus_encode_float.argtypes = [ctypes.c_void_p, ctypes.POINTER(ctypes.c_float), ctypes.c_int,
+                                          ctypes.c_char_p, ctypes.c_int32]
+    libopus.opus_encoder_destroy.argtypes = [ctypes.c_void_p]
+    return libopus
+
+
+def opus_packets(libopus):
+    """Encode a 440Hz tone, returning the packets and the encoder delay (the Ogg pre-skip)"""
+    error = ctypes.c_int()
+    encoder = libopus.opus_encoder_create(SAMPLE_RATE, 1, OPUS_APPLICATION_AUDIO, ctypes.byref(error))
+    if error.value != 0:
+        raise RuntimeError(f"opus_encoder_create failed with {error.value}")
+    libopus.opus_encoder_ctl(ctypes.c_void_p(encoder), OPUS_SET_BITRATE_REQUEST, ctypes.c_int32(64000))
+    lookahead = ctypes.c_int32()
+    libopus.opus_encoder_ctl(ctypes.c_void_p(encoder), OPUS_GET_LOOKAHEAD_REQUEST, ctypes.byref(lookahead))
+
+    # The decoder's first pre-skip samples are encoder delay, so encode that much silence past the tone
+    frames = -(-(SAMPLE_RATE + lookahead.value) // OPUS_FRAME)
+    tone = [0.5 * math.sin(2 * math.pi * 440 * i / SAMPLE_RATE) for i in range(SAMPLE_RATE)]
+    tone += [0.0] * (frames * OPUS_FRAME - len(tone))
+
+    packets = []
+    output = ctypes.create_string_buffer(4000)
+    for frame in range(frames):
+        pcm = (ctypes.c_float * OPUS_FRAME)(*tone[frame * OPUS_FRAME:(frame + 1) * OPUS_FRAME])
+        length = libopus.opus_encode_float(encoder, pcm, OPUS_FRAME, output, len(output))
+        if length < 0:
+            raise RuntimeError(f"opus_encode_float failed with {length}")
+        packets.append(output.raw[:length])
+    libopus.opus_encoder_destroy(encoder)
+    return packets, lookahead.value
+
+
+def ogg_crc(data):
+    crc = 0
+    for byte in data:
+        crc ^= byte << 24
+        for _ in range(8):
+            crc = ((crc << 1) ^ 0x04C11DB7 if crc & 0x80000000 else crc << 1) & 0xFFFFFFFF
+    return crc
+
+
+def ogg_page(packets, granule, sequence, flags):
+    lacing = b"".join(bytes([255] * (len(packet) // 255) + [len(packet) % 255]) for packet in packets)
+    header = b"OggS" + struct.pack("<BBqIII", 0, flags, granule, 1, sequence, 0) + bytes([len(lacing)]) + lacing
+    page = header + b"".join(packets)
+    return page[:22] + struct.pack("<I", ogg_crc(page)) + page[26:]
+
+
+def ogg_opus_file(packets, pre_skip, packets_per_page=10):
+    head = b"OpusHead" + struct.pack("<BBHIhB", 1, 1, pre_skip, SAMPLE_RATE, 0, 0)
+    vendor = b"make-test-fixtures"
+    tags = b"OpusTags" + struct.pack("<I", len(vendor)) + vendor + struct.pack("<I", 0)
+    pages = [ogg_page([head], 0, 0, 0x02), ogg_page([tags], 0, 1, 0)]
+
+    # The last page's granule position ends the stream exactly one second after the pre-skip
+    for start in range(0, len(packets), packets_per_page):
+        page_packets = packets[start:start + packets_per_page]
+        last = start + packets_per_page >= len(packets)
+        granule = pre_skip + SAMPLE_RATE if last else (start + len(page_packets)) * OPUS_FRAME
+        pages.append(ogg_page(page_packets, granule, len(pages), 0x04 if last else 0))
+    return b"".join(pages)
+
+
+def main():
+    os.makedirs(FIXTURES_DIR, exist_ok=True)
+    outputs = {
+        "tone.mp3": b"".join(mp3_frame(i) for i in range(42)),
+        "tone.m4a": m4a_file([aac_frame(i) for i in range(47)]),
+    }
+    libopus = load_libopus()
+    if libopus is None:
+        print("libopus not found, skipping tone.opus (set LIBOPUS to the library's path)")
+    else:
+        outputs["tone.opus"] = ogg_opus_file(*opus_packets(libopus))
+    for name, data in outputs.items():
+        path = os.path.join(FIXTURES_DIR, name)
+        with open(path, "wb") as file:
+            file.write(data)
+        print(f"Wrote {os.path.normpath(path)} ({len(data)} bytes)")
+
+
+if __name__ == "__main__":
+    sys.exit(main())
@@ -1,30 +1,78 @@
 use crate::config::*;
 use crate::error::{Result, VoiceError};
+use crate::audio_source::{AudioSink, AudioSource, InputDeviceInfo, SystemSource};
+use crate::recording::RecordedAudio;
+use crate::resample::{resample, ResampleQuality};
+use crate::ring_buffer::{ring_buffer, Consumer};
 use crate::platform::debug_eprintln;
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use hound::{WavWriter, WavSpec, WavReader};
-use std::sync::{Arc, Mutex};
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::path::PathBuf;
+use symphonia::core::codecs::CodecRegistry;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use std::path::{Path, PathBuf};
 use std::fs;
+use std::io::Read;
 use chrono::Utc;
 
+/// Mono audio samples together with the sample rate they were captured or decoded at
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AudioBuffer {
+    pub samples: Vec<f32>,
+    pub sample_rate: u32,
+}
+
+impl AudioBuffer {
+    pub fn new(samples: Vec<f32>, sample_rate: u32) -> Self {
+        Self { samples, sample_rate }
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn duration_seconds(&self) -> f32 {
+        if self.sample_rate == 0 {
+            return 0.0;
+        }
+        self.samples.len() as f32 / self.sample_rate as f32
+    }
+}
+
+/// Root mean square level of a block of samples
+fn rms(samples: &[f32]) -> f32 {
+    (samples.iter().map(|&x| x * x).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
 pub struct AudioProcessor {
     debug_enabled: bool,
+    resample_quality: ResampleQuality,
 }
 
 impl AudioProcessor {
     pub fn new(debug_enabled: bool) -> Self {
-        Self { debug_enabled }
+        Self { debug_enabled, resample_quality: ResampleQuality::default() }
+    }
+
+    /// Resample with `quality` instead of the default
+    pub fn with_resample_quality(mut self, quality: ResampleQuality) -> Self {
+        self.resample_quality = quality;
+        self
     }
 
     /// Prepare audio for Whisper transcription (convert to 16kHz mono)
-    pub fn prepare_for_whisper(&self, audio_data: &[f32]) -> Result<Vec<f32>> {
-        // Whisper expects 16kHz mono audio
-        // Most audio capture happens at 44.1kHz, so we need to resample
-        
-        // First, resample from 44.1kHz to 16kHz using simple decimation
-        let resampled = self.resample_audio(audio_data, DEFAULT_SAMPLE_RATE, WHISPER_SAMPLE_RATE);
+    pub fn prepare_for_whisper(&self, audio: &AudioBuffer) -> Result<AudioBuffer> {
+        if audio.sample_rate == 0 {
+            return Err(VoiceError::AudioProcessing("Audio has a sample rate of 0Hz".to_string()));
+        }
+
+        // Whisper expects 16kHz mono audio, so resample from whatever rate the source produced
+        let resampled = self.resample_audio(&audio.samples, audio.sample_rate, WHISPER_SAMPLE_RATE);
         
         // Then normalize audio to prevent clipping
         let max_amplitude = resampled.iter()
@@ -32,74 +80,73 @@ impl AudioProcessor {
             .max_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
             .unwrap_or(1.0);
             
-        if max_amplitude > 0.0 {
-            Ok(resampled.iter().map(|&x| x / max_amplitude).collect())
+        let samples = if max_amplitude > 0.0 {
+            resampled.iter().map(|&x| x / max_amplitude).collect()
         } else {
-            Ok(resampled)
-        }
+            resampled
+        };
+
+        Ok(AudioBuffer::new(samples, WHISPER_SAMPLE_RATE))
     }
 
-    /// Resample audio from one sample rate to another
+    /// Resample audio from one sample rate to another with a band-limited filter, so content
+    /// above the new Nyquist frequency is removed instead of aliasing into the speech band
     pub fn resample_audio(&self, audio_data: &[f32], input_rate: u32, output_rate: u32) -> Vec<f32> {
-        if input_rate == output_rate {
+        if input_rate == output_rate || audio_data.is_empty() {
             return audio_data.to_vec();
         }
-        
-        let ratio = input_rate as f64 / output_rate as f64;
-        let output_length = (audio_data.len() as f64 / ratio).ceil() as usize;
-        let mut resampled = Vec::with_capacity(output_length);
-        
-        // Simple linear interpolation resampling
-        for i in 0..output_length {
-            let input_index = i as f64 * ratio;
-            let index_floor = input_index.floor() as usize;
-            let index_ceil = (index_floor + 1).min(audio_data.len() - 1);
-            let fraction = input_index - index_floor as f64;
-            
-            if index_floor >= audio_data.len() {
-                break;
+
+        let resampled = match resample(audio_data, input_rate, output_rate, self.resample_quality) {
+            Ok(resampled) => resampled,
+            Err(e) => {
+                debug_eprintln!(self.debug_enabled, "Warning: {}; using the audio as is", e);
+                return audio_data.to_vec();
             }
-            
-            let sample = if index_floor == index_ceil {
-                audio_data[index_floor]
-            } else {
-                // Linear interpolation
-                let sample1 = audio_data[index_floor];
-                let sample2 = audio_data[index_ceil];
-                sample1 + (sample2 - sample1) * fraction as f32
-            };
-            
-            resampled.push(sample);
-        }
+        };
         
-        debug_eprintln!(self.debug_enabled, "🔄 Resampled {} samples ({}Hz) -> {} samples ({}Hz)", 
-                audio_data.len(), input_rate, resampled.len(), output_rate);
+        debug_eprintln!(self.debug_enabled, "🔄 Resampled {} samples ({}Hz) -> {} samples ({}Hz), {} quality", 
+                audio_data.len(), input_rate, resampled.len(), output_rate, self.resample_quality);
         
         resampled
     }
 
     /// Check if recent audio contains voice activity
-    pub fn has_voice_activity(&self, audio_data: &[f32]) -> bool {
-        if audio_data.len() < samples_for_duration_ms(DEFAULT_SAMPLE_RATE, RECENT_SAMPLES_DURATION_MS) {
+    pub fn has_voice_activity(&self, audio_data: &[f32], sample_rate: u32) -> bool {
+        let recent_len = samples_for_duration_ms(sample_rate, RECENT_SAMPLES_DURATION_MS);
+        if recent_len == 0 || audio_data.len() < recent_len {
             return false;
         }
         
-        let recent_samples = &audio_data[audio_data.len().saturating_sub(
-            samples_for_duration_ms(DEFAULT_SAMPLE_RATE, RECENT_SAMPLES_DURATION_MS)
-        )..];
-        
-        let rms = (recent_samples.iter().map(|&x| x * x).sum::<f32>() / recent_samples.len() as f32).sqrt();
-        rms > SILENCE_THRESHOLD
+        let recent_samples = &audio_data[audio_data.len() - recent_len..];
+        rms(recent_samples) > SILENCE_THRESHOLD
+    }
+
+    /// Index of the quietest point in `audio_data`: the middle of the frame with the lowest RMS,
+    /// using the same frame length as `has_voice_activity`
+    pub fn quietest_point(&self, audio_data: &[f32], sample_rate: u32) -> usize {
+        let frame_len = samples_for_duration_ms(sample_rate, RECENT_SAMPLES_DURATION_MS).max(1);
+        if audio_data.len() <= frame_len {
+            return audio_data.len() / 2;
+        }
+
+        // Step by half a frame so a short pause between words is not missed
+        let step = (frame_len / 2).max(1);
+        let (start, _) = (0..=audio_data.len() - frame_len)
+            .step_by(step)
+            .map(|start| (start, rms(&audio_data[start..start + frame_len])))
+            .fold((0, f32::INFINITY), |quietest, frame| if frame.1 < quietest.1 { frame } else { quietest });
+        start + frame_len / 2
     }
 
     /// Validate audio for transcription
-    pub fn validate_audio(&self, audio_data: &[f32], sample_rate: u32) -> Result<()> {
-        let duration_seconds = audio_data.len() as f32 / sample_rate as f32;
+    pub fn validate_audio(&self, audio: &AudioBuffer) -> Result<()> {
+        let audio_data = &audio.samples;
+        let duration_seconds = audio.duration_seconds();
         let max_amplitude = audio_data.iter()
             .map(|&x| x.abs())
             .max_by(|a, b| a.partial_cmp(b).unwrap())
             .unwrap_or(0.0);
-        let rms = (audio_data.iter().map(|&x| x * x).sum::<f32>() / audio_data.len() as f32).sqrt();
+        let rms = rms(audio_data);
         
         debug_eprintln!(self.debug_enabled, "🎤 Audio stats: {:.2}s duration, max amplitude: {:.4}, RMS: {:.4}", 
                 duration_seconds, max_amplitude, rms);
@@ -119,123 +166,335 @@ impl AudioProcessor {
 
 pub struct AudioCapture {
     is_recording: Arc<AtomicBool>,
-    audio_data: Arc<Mutex<Vec<f32>>>,
-    audio_stream: Arc<Mutex<Option<cpal::Stream>>>,
+    audio_data: Arc<Mutex<RecordedAudio>>,
+    sample_rate: Arc<AtomicU32>,
+    source: Box<dyn AudioSource>,
+    /// Moves samples from the source's ring buffer into `audio_data`
+    collector: Mutex<Option<Collector>>,
+    /// Captured audio beyond this many bytes is spilled to a temporary file
+    memory_limit: usize,
+    /// Length of the last recording, which `stop_capture` hands over to the caller
+    last_sample_count: AtomicUsize,
     debug_enabled: bool,
 }
 
+struct Collector {
+    running: Arc<AtomicBool>,
+    handle: JoinHandle<CaptureLoss>,
+}
+
+/// What the collector failed to record, reported when the capture stops
+#[derive(Default)]
+struct CaptureLoss {
+    /// Samples the ring buffer had no room for
+    dropped: usize,
+    /// First error appending to the recording, e.g. spilling to a full disk
+    append_error: Option<VoiceError>,
+}
+
 impl AudioCapture {
     pub fn new(debug_enabled: bool) -> Self {
+        Self::with_source(Box::new(SystemSource::new(debug_enabled)), debug_enabled)
+    }
+
+    /// Capture from `source` instead of the system's input devices
+    pub fn with_source(source: Box<dyn AudioSource>, debug_enabled: bool) -> Self {
+        let memory_limit = DEFAULT_MAX_MEMORY_MB * 1024 * 1024;
         Self {
             is_recording: Arc::new(AtomicBool::new(false)),
-            audio_data: Arc::new(Mutex::new(Vec::new())),
-            audio_stream: Arc::new(Mutex::new(None)),
+            audio_data: Arc::new(Mutex::new(RecordedAudio::new(DEFAULT_SAMPLE_RATE, memory_limit))),
+            sample_rate: Arc::new(AtomicU32::new(DEFAULT_SAMPLE_RATE)),
+            source,
+            collector: Mutex::new(None),
+            memory_limit,
+            last_sample_count: AtomicUsize::new(0),
             debug_enabled,
         }
     }
 
+    /// Replace the audio source used by the next recording
+    pub fn set_source(&mut self, source: Box<dyn AudioSource>) -> Result<()> {
+        if self.is_recording() {
+            return Err(VoiceError::AlreadyRecording);
+        }
+        self.source = source;
+        Ok(())
+    }
+
+    /// Keep at most `bytes` of captured audio in memory; later recordings spill the rest to disk
+    pub fn set_memory_limit(&mut self, bytes: usize) {
+        self.memory_limit = bytes;
+    }
+
+    pub fn memory_limit(&self) -> usize {
+        self.memory_limit
+    }
+
     pub fn is_recording(&self) -> bool {
         self.is_recording.load(Ordering::Relaxed)
     }
 
     pub fn get_audio_sample_count(&self) -> usize {
-        self.audio_data.lock().unwrap().len()
+        if self.is_recording() {
+            self.audio_data.lock().unwrap().len()
+        } else {
+            self.last_sample_count.load(Ordering::Relaxed)
+        }
+    }
+
+    /// Sample rate of the source feeding the current (or most recent) recording
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate.load(Ordering::Relaxed)
     }
 
     pub fn start_capture(&self) -> Result<()> {
+        self.start_capture_on_device(None)
+    }
+
+    /// Start recording from the named input device, or the system default when `device` is `None`
+    pub fn start_capture_on_device(&self, device: Option<&str>) -> Result<()> {
         if self.is_recording.load(Ordering::Relaxed) {
             return Err(VoiceError::AlreadyRecording);
         }
 
         self.is_recording.store(true, Ordering::Relaxed);
+        self.last_sample_count.store(0, Ordering::Relaxed);
         
         // Clear previous audio data
-        {
-            let mut data = self.audio_data.lock().unwrap();
-            data.clear();
+        *self.audio_data.lock().unwrap() = RecordedAudio::new(self.sample_rate(), self.memory_limit);
+
+        // Start audio capture; the source writes into a ring buffer that the collector drains
+        let (producer, consumer) = ring_buffer(CAPTURE_RING_CAPACITY);
+        let sink = AudioSink::new(producer, Arc::clone(&self.is_recording));
+        match self.source.start(device, sink) {
+            Ok(sample_rate) => {
+                self.sample_rate.store(sample_rate, Ordering::Relaxed);
+                self.audio_data.lock().unwrap().set_sample_rate(sample_rate);
+                *self.collector.lock().unwrap() = Some(self.spawn_collector(consumer));
+                Ok(())
+            }
+            Err(e) => {
+                self.is_recording.store(false, Ordering::Relaxed);
+                Err(e)
+            }
         }
+    }
+
+    fn spawn_collector(&self, mut consumer: Consumer) -> Collector {
+        let running = Arc::new(AtomicBool::new(true));
+        let audio_data = Arc::clone(&self.audio_data);
+        let collector_running = Arc::clone(&running);
+        let debug_enabled = self.debug_enabled;
+        let handle = std::thread::spawn(move || {
+            let mut pending = Vec::new();
+            let mut loss = CaptureLoss::default();
+            loop {
+                // Read the flag before draining so samples pushed before a stop are never lost
+                let keep_running = collector_running.load(Ordering::Acquire);
+                if consumer.pop_into(&mut pending) > 0 {
+                    if let Err(e) = audio_data.lock().unwrap().append(&pending) {
+                        debug_eprintln!(debug_enabled, "⚠️ Failed to spill captured audio to disk: {}", e);
+                        loss.append_error.get_or_insert(e);
+                    }
+                    pending.clear();
+                }
+                if !keep_running {
+                    loss.dropped = consumer.dropped();
+                    return loss;
+                }
+                std::thread::sleep(Duration::from_millis(CAPTURE_DRAIN_INTERVAL_MS));
+            }
+        });
+        Collector { running, handle }
+    }
+
+    /// Wait for the collector to move the last buffered samples into `audio_data`, failing if
+    /// any captured audio didn't make it there
+    fn stop_collector(&self) -> Result<()> {
+        let Some(collector) = self.collector.lock().unwrap().take() else {
+            return Ok(());
+        };
+        collector.running.store(false, Ordering::Release);
+        let Ok(loss) = collector.handle.join() else {
+            return Ok(());
+        };
 
-        // Start audio capture
-        self.start_audio_stream()?;
+        if let Some(e) = loss.append_error {
+            return Err(VoiceError::CaptureLost(format!("storing the recording failed: {}", e)));
+        }
+        if loss.dropped > 0 {
+            debug_eprintln!(self.debug_enabled, "⚠️ Dropped {} samples because the capture buffer was full", loss.dropped);
+            return Err(VoiceError::CaptureLost(format!(
+                "{} samples were dropped because the capture buffer was full", loss.dropped
+            )));
+        }
         Ok(())
     }
 
-    pub fn stop_capture(&self) -> Result<Vec<f32>> {
+    /// Stop recording and hand over the captured audio, which may be partly on disk
+    pub fn stop_capture(&self) -> Result<RecordedAudio> {
         if !self.is_recording.load(Ordering::Relaxed) {
             return Err(VoiceError::NotRecording);
         }
 
         self.is_recording.store(false, Ordering::Relaxed);
+        self.source.stop();
+        let collected = self.stop_collector();
+
+        let mut data = self.audio_data.lock().unwrap();
+        let recording = std::mem::replace(&mut *data, RecordedAudio::new(self.sample_rate(), self.memory_limit));
+        self.last_sample_count.store(recording.len(), Ordering::Relaxed);
+        // A recording with gaps would come back as a silently truncated transcription
+        collected?;
+        Ok(recording)
+    }
 
-        // Stop and drop the audio stream
-        {
-            let mut stream_guard = self.audio_stream.lock().unwrap();
-            *stream_guard = None;
+    /// Stop recording and discard the captured audio
+    pub fn cancel_capture(&self) -> Result<()> {
+        match self.stop_capture() {
+            // Nothing is kept, so lost samples don't matter
+            Ok(_) | Err(VoiceError::CaptureLost(_)) => Ok(()),
+            Err(e) => Err(e),
         }
+    }
 
-        // Get audio data
-        let audio_data = {
-            let data = self.audio_data.lock().unwrap();
-            data.clone()
-        };
+    /// Everything recorded so far
+    pub fn get_current_audio_data(&self) -> Result<AudioBuffer> {
+        self.audio_data.lock().unwrap().to_buffer()
+    }
 
-        Ok(audio_data)
+    /// Audio recorded from sample index `start` onwards, without copying the earlier part.
+    ///
+    /// Polling with the previous length as `start` reads a long recording incrementally.
+    pub fn get_audio_since(&self, start: usize) -> Result<AudioBuffer> {
+        self.audio_data.lock().unwrap().read_from(start)
     }
 
-    pub fn get_current_audio_data(&self) -> Vec<f32> {
-        self.audio_data.lock().unwrap().clone()
+    /// Up to `count` samples recorded from sample index `start`
+    pub fn get_audio_range(&self, start: usize, count: usize) -> Result<AudioBuffer> {
+        self.audio_data.lock().unwrap().read(start, count)
     }
 
-    fn start_audio_stream(&self) -> Result<()> {
-        let host = cpal::default_host();
-        let device = host
-            .default_input_device()
-            .ok_or(VoiceError::NoInputDevice)?;
+    /// Free the audio before sample index `index` while the recording continues; see
+    /// `RecordedAudio::discard_before`
+    pub fn discard_audio_before(&self, index: usize) {
+        self.audio_data.lock().unwrap().discard_before(index);
+    }
 
-        let config = device.default_input_config()?;
-        let _sample_rate = config.sample_rate().0;
-        let channels = config.channels();
+    /// Describe every input device of the default audio host
+    pub fn list_input_devices() -> Result<Vec<InputDeviceInfo>> {
+        SystemSource::list_input_devices()
+    }
+}
 
-        let audio_data = Arc::clone(&self.audio_data);
-        let is_recording = Arc::clone(&self.is_recording);
-        let debug_enabled = self.debug_enabled;
+impl Drop for AudioCapture {
+    fn drop(&mut self) {
+        // Let the collector thread exit if the capture is dropped mid-recording
+        if let Some(collector) = self.collector.lock().unwrap().take() {
+            collector.running.store(false, Ordering::Release);
+        }
+    }
+}
 
-        let stream = device.build_input_stream(
-            &config.into(),
-            move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                if is_recording.load(Ordering::Relaxed) {
-                    let mut audio_buffer = audio_data.lock().unwrap();
-                    
-                    // Convert to mono if stereo
-                    if channels == 1 {
-                        audio_buffer.extend_from_slice(data);
-                    } else {
-                        // Convert stereo to mono by averaging channels
-                        for chunk in data.chunks(channels as usize) {
-                            let mono_sample = chunk.iter().sum::<f32>() / channels as f32;
-                            audio_buffer.push(mono_sample);
-                        }
-                    }
-                }
-            },
-            move |err| {
-                debug_eprintln!(debug_enabled, "Audio stream error: {}", err);
-            },
-            None,
-        )?;
-
-        stream.play()?;
-        
-        // Store the stream to keep it alive during recording
-        {
-            let mut stream_guard = self.audio_stream.lock().unwrap();
-            *stream_guard = Some(stream);
+/// Container/codec of an audio file, detected from its leading bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioFormat {
+    Wav,
+    Mp3,
+    Flac,
+    OggVorbis,
+    OggOpus,
+    /// Raw AAC in an ADTS stream
+    Aac,
+    /// MP4/M4A container (AAC audio)
+    Mp4,
+}
+
+impl AudioFormat {
+    /// Number of leading bytes needed by `detect`
+    pub const HEADER_LEN: usize = 64;
+
+    /// Detect the audio format from the magic bytes at the start of a file
+    pub fn detect(header: &[u8]) -> Option<Self> {
+        if header.len() >= 12 && &header[0..4] == b"RIFF" && &header[8..12] == b"WAVE" {
+            return Some(AudioFormat::Wav);
+        }
+        if header.starts_with(b"fLaC") {
+            return Some(AudioFormat::Flac);
+        }
+        if header.starts_with(b"OggS") {
+            // The first Ogg page carries the codec identification header
+            let first_page = &header[..header.len().min(Self::HEADER_LEN)];
+            if contains_bytes(first_page, b"OpusHead") {
+                return Some(AudioFormat::OggOpus);
+            }
+            if contains_bytes(first_page, b"\x01vorbis") {
+                return Some(AudioFormat::OggVorbis);
+            }
+            return None;
+        }
+        if header.len() >= 8 && &header[4..8] == b"ftyp" {
+            return Some(AudioFormat::Mp4);
+        }
+        if header.starts_with(b"ID3") {
+            return Some(AudioFormat::Mp3);
         }
+        if header.len() >= 2 && header[0] == 0xFF {
+            // ADTS sync word has layer bits 00, MPEG audio frames have a non-zero layer
+            if header[1] & 0xF6 == 0xF0 {
+                return Some(AudioFormat::Aac);
+            }
+            if header[1] & 0xE0 == 0xE0 && header[1] & 0x06 != 0 {
+                return Some(AudioFormat::Mp3);
+            }
+        }
+        None
+    }
 
-        Ok(())
+    pub fn name(&self) -> &'static str {
+        match self {
+            AudioFormat::Wav => "WAV",
+            AudioFormat::Mp3 => "MP3",
+            AudioFormat::Flac => "FLAC",
+            AudioFormat::OggVorbis => "Ogg Vorbis",
+            AudioFormat::OggOpus => "Ogg Opus",
+            AudioFormat::Aac => "AAC (ADTS)",
+            AudioFormat::Mp4 => "MP4/M4A",
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            AudioFormat::Wav => "wav",
+            AudioFormat::Mp3 => "mp3",
+            AudioFormat::Flac => "flac",
+            AudioFormat::OggVorbis => "ogg",
+            AudioFormat::OggOpus => "opus",
+            AudioFormat::Aac => "aac",
+            AudioFormat::Mp4 => "m4a",
+        }
     }
 }
 
+fn contains_bytes(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|window| window == needle)
+}
+
+/// Symphonia's decoders, plus libopus for Ogg Opus when the `opus` feature is enabled
+fn codec_registry() -> &'static CodecRegistry {
+    static CODECS: OnceLock<CodecRegistry> = OnceLock::new();
+    CODECS.get_or_init(|| {
+        let mut codecs = CodecRegistry::new();
+        symphonia::default::register_enabled_codecs(&mut codecs);
+        #[cfg(feature = "opus")]
+        codecs.register_all::<crate::opus::OpusDecoder>();
+        codecs
+    })
+}
+
+/// Mono samples handed on at a time while decoding a WAV file
+const WAV_DECODE_BLOCK: usize = 65536;
+
 pub struct AudioFileHandler {
     debug_config: DebugConfig,
 }
@@ -245,7 +504,166 @@ impl AudioFileHandler {
         Self { debug_config }
     }
 
-    pub fn load_wav_file(&self, wav_path: &str) -> Result<Vec<f32>> {
+    /// Load any supported audio file, picking the decoder from the file's magic bytes
+    pub fn load_audio_file(&self, path: &str) -> Result<AudioBuffer> {
+        let mut samples = Vec::new();
+        let sample_rate = self.decode_audio_file(path, |chunk| {
+            samples.extend_from_slice(chunk);
+            Ok(())
+        })?;
+        Ok(AudioBuffer::new(samples, sample_rate))
+    }
+
+    /// Decode an audio file into a recording that keeps at most `memory_limit` bytes in memory,
+    /// so arbitrarily long files can be transcribed in chunks
+    pub fn load_audio_file_into_recording(&self, path: &str, memory_limit: usize) -> Result<RecordedAudio> {
+        let mut recording = RecordedAudio::new(0, memory_limit);
+        let sample_rate = self.decode_audio_file(path, |chunk| recording.append(chunk))?;
+        recording.set_sample_rate(sample_rate);
+        Ok(recording)
+    }
+
+    /// Decode a file as a stream of mono sample blocks and return its sample rate
+    fn decode_audio_file(&self, path: &str, mut on_samples: impl FnMut(&[f32]) -> Result<()>) -> Result<u32> {
+        let format = self.detect_format(path)?;
+        debug_eprintln!(self.debug_config.enabled, "📁 Detected {} audio: {}", format.name(), path);
+
+        match format {
+            AudioFormat::Wav => self.decode_wav_file(path, &mut on_samples),
+            #[cfg(not(feature = "opus"))]
+            AudioFormat::OggOpus => Err(VoiceError::UnsupportedCodec(
+                "Opus (this build has no Opus decoder; rebuild with the `opus` feature)".to_string()
+            )),
+            _ => self.decode_compressed_file(path, format, &mut on_samples),
+        }
+    }
+
+    /// Detect the container/codec of an audio file from its leading bytes
+    pub fn detect_format(&self, path: &str) -> Result<AudioFormat> {
+        let mut header = Vec::with_capacity(AudioFormat::HEADER_LEN);
+        fs::File::open(path)?
+            .take(AudioFormat::HEADER_LEN as u64)
+            .read_to_end(&mut header)?;
+
+        AudioFormat::detect(&header).ok_or_else(|| {
+            let extension = Path::new(path)
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .unwrap_or("unknown");
+            VoiceError::UnsupportedAudioFormat(format!(
+                "{} (.{}) - supported formats are WAV, MP3, FLAC, Ogg Vorbis/Opus and AAC/M4A", path, extension
+            ))
+        })
+    }
+
+    fn decode_compressed_file(&self, path: &str, format: AudioFormat, on_samples: &mut dyn FnMut(&[f32]) -> Result<()>) -> Result<u32> {
+        use symphonia::core::audio::SampleBuffer;
+        use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+        use symphonia::core::errors::Error as SymphoniaError;
+        use symphonia::core::formats::FormatOptions;
+        use symphonia::core::io::MediaSourceStream;
+        use symphonia::core::meta::MetadataOptions;
+        use symphonia::core::probe::Hint;
+
+        let file = fs::File::open(path)?;
+        let stream = MediaSourceStream::new(Box::new(file), Default::default());
+
+        let mut hint = Hint::new();
+        hint.with_extension(format.extension());
+
+        // Ogg only trims the encoder padding after the last Opus sample in gapless mode; the
+        // decoder drops the pre-skip at the start itself
+        let format_options = FormatOptions { enable_gapless: format == AudioFormat::OggOpus, ..Default::default() };
+        let probed = symphonia::default::get_probe()
+            .format(&hint, stream, &format_options, &MetadataOptions::default())?;
+        let mut reader = probed.format;
+
+        // Pick the first audio track and make a decoder for it
+        let open_track = |reader: &dyn symphonia::core::formats::FormatReader| -> Result<_> {
+            let track = reader.tracks()
+                .iter()
+                .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+                .ok_or_else(|| VoiceError::AudioDecode(format!("No audio track found in {}", path)))?;
+            let sample_rate = track.codec_params.sample_rate
+                .ok_or_else(|| VoiceError::AudioDecode("Audio track has no sample rate".to_string()))?;
+            let decoder = codec_registry().make(&track.codec_params, &DecoderOptions::default())?;
+            Ok((track.id, sample_rate, decoder))
+        };
+        let (mut track_id, sample_rate, mut decoder) = open_track(reader.as_ref())?;
+
+        let mut sample_count = 0;
+        let mut mono = Vec::new();
+        let mut sample_buffer: Option<SampleBuffer<f32>> = None;
+
+        loop {
+            let packet = match reader.next_packet() {
+                Ok(packet) => packet,
+                // The demuxer signals the end of the stream with an unexpected EOF
+                Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+                Err(SymphoniaError::ResetRequired) => {
+                    // A new logical stream begins (e.g. a chained Ogg file); carry on with its track
+                    let (next_track_id, next_sample_rate, next_decoder) = open_track(reader.as_ref())?;
+                    if next_sample_rate != sample_rate {
+                        return Err(VoiceError::AudioDecode(format!(
+                            "Sample rate changes from {}Hz to {}Hz part-way through {}", sample_rate, next_sample_rate, path
+                        )));
+                    }
+                    track_id = next_track_id;
+                    decoder = next_decoder;
+                    sample_buffer = None;
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            if packet.track_id() != track_id {
+                continue;
+            }
+
+            let decoded = match decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                Err(SymphoniaError::DecodeError(e)) => {
+                    // Corrupt frames are skipped rather than failing the whole file
+                    debug_eprintln!(self.debug_config.enabled, "⚠️ Skipping undecodable packet: {}", e);
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            let spec = *decoded.spec();
+            let channels = spec.channels.count().max(1);
+            let buffer = match sample_buffer.as_mut() {
+                Some(buffer) if buffer.capacity() >= decoded.capacity() * channels => buffer,
+                _ => sample_buffer.insert(SampleBuffer::new(decoded.capacity() as u64, spec)),
+            };
+            buffer.copy_interleaved_ref(decoded);
+
+            // Downmix interleaved frames to mono
+            mono.clear();
+            mono.extend(
+                buffer.samples()
+                    .chunks(channels)
+                    .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+            );
+            sample_count += mono.len();
+            on_samples(&mono)?;
+        }
+
+        debug_eprintln!(self.debug_config.enabled, "📊 Decoded {} samples at {}Hz from {} file", 
+                sample_count, sample_rate, format.name());
+        Ok(sample_rate)
+    }
+
+    pub fn load_wav_file(&self, wav_path: &str) -> Result<AudioBuffer> {
+        let mut samples = Vec::new();
+        let sample_rate = self.decode_wav_file(wav_path, &mut |chunk| {
+            samples.extend_from_slice(chunk);
+            Ok(())
+        })?;
+        Ok(AudioBuffer::new(samples, sample_rate))
+    }
+
+    fn decode_wav_file(&self, wav_path: &str, on_samples: &mut dyn FnMut(&[f32]) -> Result<()>) -> Result<u32> {
         debug_eprintln!(self.debug_config.enabled, "📁 Loading WAV file: {}", wav_path);
         
         // Read the WAV file
@@ -254,36 +672,45 @@ impl AudioFileHandler {
         
         debug_eprintln!(self.debug_config.enabled, "🎵 WAV specs: {}Hz, {} channels, {} bits", 
                 spec.sample_rate, spec.channels, spec.bits_per_sample);
+        if spec.channels > 1 {
+            debug_eprintln!(self.debug_config.enabled, "🔄 Converting {} channels to mono", spec.channels);
+        }
         
-        // Read all samples as f32
-        let samples: std::result::Result<Vec<f32>, _> = match spec.sample_format {
-            hound::SampleFormat::Float => {
-                reader.samples::<f32>().collect()
-            }
+        // Read samples as f32 in blocks so long files never need to fit in memory
+        let samples: Box<dyn Iterator<Item = std::result::Result<f32, hound::Error>>> = match spec.sample_format {
+            hound::SampleFormat::Float => Box::new(reader.samples::<f32>()),
             hound::SampleFormat::Int => {
-                reader.samples::<i32>().map(|s| s.map(|sample| {
-                    // Convert integer samples to float [-1.0, 1.0]
-                    sample as f32 / (1i32 << (spec.bits_per_sample - 1)) as f32
-                })).collect()
+                // Convert integer samples to float [-1.0, 1.0]
+                let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+                Box::new(reader.samples::<i32>().map(move |s| s.map(|sample| sample as f32 / scale)))
             }
         };
         
-        let mut audio_data = samples.map_err(|e| VoiceError::WavFile(e.to_string()))?;
-        
-        // Convert stereo to mono if needed
-        if spec.channels == 2 {
-            debug_eprintln!(self.debug_config.enabled, "🔄 Converting stereo to mono");
-            let mono_data: Vec<f32> = audio_data.chunks(2)
-                .map(|chunk| (chunk[0] + chunk.get(1).unwrap_or(&0.0)) / 2.0)
-                .collect();
-            audio_data = mono_data;
+        let channels = spec.channels.max(1) as usize;
+        let mut frame = Vec::with_capacity(channels);
+        let mut mono = Vec::with_capacity(WAV_DECODE_BLOCK);
+        let mut sample_count = 0;
+        for sample in samples {
+            frame.push(sample.map_err(|e| VoiceError::WavFile(e.to_string()))?);
+            if frame.len() == channels {
+                // Downmix to mono if needed
+                mono.push(frame.iter().sum::<f32>() / channels as f32);
+                frame.clear();
+            }
+            if mono.len() == WAV_DECODE_BLOCK {
+                sample_count += mono.len();
+                on_samples(&mono)?;
+                mono.clear();
+            }
         }
+        sample_count += mono.len();
+        on_samples(&mono)?;
         
-        debug_eprintln!(self.debug_config.enabled, "📊 Loaded {} samples from WAV file", audio_data.len());
-        Ok(audio_data)
+        debug_eprintln!(self.debug_config.enabled, "📊 Loaded {} samples from WAV file", sample_count);
+        Ok(spec.sample_rate)
     }
 
-    pub fn save_debug_audio(&self, audio_data: &[f32], suffix: &str, sample_rate: u32) -> Result<()> {
+    pub fn save_debug_audio(&self, audio: &AudioBuffer, suffix: &str) -> Result<()> {
         if !self.debug_config.enabled {
             return Ok(());
         }
@@ -293,7 +720,7 @@ impl AudioFileHandler {
 
         let spec = WavSpec {
             channels: 1,
-            sample_rate,
+            sample_rate: audio.sample_rate,
             bits_per_sample: 32,
             sample_format: hound::SampleFormat::Float,
         };
@@ -301,7 +728,7 @@ impl AudioFileHandler {
         let mut writer = WavWriter::create(&filepath, spec)
             .map_err(|e| VoiceError::DebugFileSave(e.to_string()))?;
         
-        for &sample in audio_data {
+        for &sample in &audio.samples {
             writer.write_sample(sample)
                 .map_err(|e| VoiceError::DebugFileSave(e.to_string()))?;
         }
@@ -309,7 +736,7 @@ impl AudioFileHandler {
         writer.finalize()
             .map_err(|e| VoiceError::DebugFileSave(e.to_string()))?;
 
-        debug_eprintln!(self.debug_config.enabled, "🔧 Debug: Saved {} samples to {}", audio_data.len(), filepath.display());
+        debug_eprintln!(self.debug_config.enabled, "🔧 Debug: Saved {} samples ({}Hz) to {}", audio.len(), audio.sample_rate, filepath.display());
         Ok(())
     }
 
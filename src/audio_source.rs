@@ -0,0 +1,381 @@
+use crate::audio::{AudioBuffer, AudioFileHandler};
+use crate::config::DebugConfig;
+use crate::error::{Result, VoiceError};
+#[cfg(feature = "microphone")]
+use crate::platform::debug_eprintln;
+use crate::ring_buffer::Producer;
+#[cfg(feature = "microphone")]
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "microphone")]
+/// Sample rates checked against each device's supported ranges when listing devices
+const COMMON_SAMPLE_RATES: [u32; 9] = [8000, 11025, 16000, 22050, 32000, 44100, 48000, 88200, 96000];
+
+/// How often replayed and generated audio is delivered
+const REPLAY_TICK_MS: u64 = 10;
+
+/// Something `AudioCapture` can record from: a microphone, a file played back in real time,
+/// a generated test signal, ...
+pub trait AudioSource: Send + Sync {
+    /// Start delivering mono audio to `sink` and return its sample rate.
+    ///
+    /// `device` picks one of several inputs where the source has a choice; other sources ignore it.
+    fn start(&self, device: Option<&str>, sink: AudioSink) -> Result<u32>;
+
+    /// Stop delivering audio; the source can be started again afterwards
+    fn stop(&self);
+}
+
+/// Frames downmixed per step in `AudioSink::push_interleaved`, so the audio thread never allocates
+const DOWNMIX_FRAMES: usize = 256;
+
+/// Where an `AudioSource` delivers its samples.
+///
+/// Pushing never blocks: samples go into a lock-free ring buffer that `AudioCapture` drains on
+/// its own thread, so it is safe to call from a realtime audio callback.
+pub struct AudioSink {
+    producer: Producer,
+    is_recording: Arc<AtomicBool>,
+}
+
+impl AudioSink {
+    pub(crate) fn new(producer: Producer, is_recording: Arc<AtomicBool>) -> Self {
+        Self { producer, is_recording }
+    }
+
+    /// Whether samples are still being recorded; sources may stop producing once this is false
+    pub fn is_recording(&self) -> bool {
+        self.is_recording.load(Ordering::Relaxed)
+    }
+
+    /// Append mono samples (ignored once the recording has stopped)
+    pub fn push(&mut self, samples: &[f32]) {
+        if self.is_recording() {
+            self.producer.push_slice(samples);
+        }
+    }
+
+    /// Average interleaved frames down to mono and append them
+    pub fn push_interleaved(&mut self, data: &[f32], channels: u16) {
+        if channels <= 1 {
+            self.push(data);
+            return;
+        }
+
+        let channels = channels as usize;
+        let mut mono = [0.0f32; DOWNMIX_FRAMES];
+        for block in data.chunks(channels * DOWNMIX_FRAMES) {
+            let mut count = 0;
+            for (sample, frame) in mono.iter_mut().zip(block.chunks(channels)) {
+                *sample = frame.iter().sum::<f32>() / channels as f32;
+                count += 1;
+            }
+            self.push(&mono[..count]);
+        }
+    }
+}
+
+/// An audio input device as reported by the system
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct InputDeviceInfo {
+    pub name: String,
+    pub is_default: bool,
+    /// Sample rate used when recording from this device
+    pub default_sample_rate: Option<u32>,
+    /// Common sample rates the device supports
+    pub sample_rates: Vec<u32>,
+    pub channels: Vec<u16>,
+}
+
+#[cfg(feature = "microphone")]
+/// Records from a system input device through cpal
+pub struct CpalSource {
+    audio_stream: Mutex<Option<cpal::Stream>>,
+    debug_enabled: bool,
+}
+
+#[cfg(feature = "microphone")]
+impl CpalSource {
+    pub fn new(debug_enabled: bool) -> Self {
+        Self {
+            audio_stream: Mutex::new(None),
+            debug_enabled,
+        }
+    }
+
+    /// Describe every input device of the default audio host
+    pub fn list_input_devices() -> Result<Vec<InputDeviceInfo>> {
+        let host = cpal::default_host();
+        let default_name = host.default_input_device().and_then(|d| d.name().ok());
+        let devices = host.input_devices().map_err(|e| VoiceError::AudioStream(e.to_string()))?;
+
+        let mut infos = Vec::new();
+        for device in devices {
+            let Ok(name) = device.name() else { continue };
+            let ranges: Vec<_> = device.supported_input_configs().map(|c| c.collect()).unwrap_or_default();
+
+            let mut channels: Vec<u16> = ranges.iter().map(|r| r.channels()).collect();
+            channels.sort_unstable();
+            channels.dedup();
+
+            let sample_rates = COMMON_SAMPLE_RATES
+                .iter()
+                .copied()
+                .filter(|&rate| ranges.iter().any(|r| (r.min_sample_rate().0..=r.max_sample_rate().0).contains(&rate)))
+                .collect();
+
+            infos.push(InputDeviceInfo {
+                is_default: default_name.as_deref() == Some(name.as_str()),
+                default_sample_rate: device.default_input_config().ok().map(|c| c.sample_rate().0),
+                name,
+                sample_rates,
+                channels,
+            });
+        }
+
+        Ok(infos)
+    }
+
+    /// Find an input device by exact name, falling back to a case-insensitive substring match
+    fn find_input_device(host: &cpal::Host, name: &str) -> Result<cpal::Device> {
+        let devices: Vec<_> = host
+            .input_devices()
+            .map_err(|e| VoiceError::AudioStream(e.to_string()))?
+            .filter_map(|d| d.name().ok().map(|n| (n, d)))
+            .collect();
+
+        let wanted = name.to_lowercase();
+        let position = devices.iter().position(|(n, _)| n == name)
+            .or_else(|| devices.iter().position(|(n, _)| n.to_lowercase().contains(&wanted)));
+
+        match position {
+            Some(index) => Ok(devices.into_iter().nth(index).map(|(_, d)| d).unwrap()),
+            None => {
+                let available: Vec<_> = devices.iter().map(|(n, _)| n.as_str()).collect();
+                Err(VoiceError::InputDeviceNotFound(format!("'{}' (available: {})", name, available.join(", "))))
+            }
+        }
+    }
+}
+
+#[cfg(feature = "microphone")]
+impl AudioSource for CpalSource {
+    fn start(&self, device_name: Option<&str>, mut sink: AudioSink) -> Result<u32> {
+        let host = cpal::default_host();
+        let device = match device_name {
+            Some(name) => Self::find_input_device(&host, name)?,
+            None => host.default_input_device().ok_or(VoiceError::NoInputDevice)?,
+        };
+
+        let config = device.default_input_config()?;
+        let sample_rate = config.sample_rate().0;
+        let channels = config.channels();
+
+        debug_eprintln!(self.debug_enabled, "🎙️ Capturing from '{}' at {}Hz, {} channel(s)",
+                device.name().unwrap_or_default(), sample_rate, channels);
+
+        let debug_enabled = self.debug_enabled;
+
+        let stream = device.build_input_stream(
+            &config.into(),
+            move |data: &[f32], _: &cpal::InputCallbackInfo| {
+                // Convert to mono if stereo
+                sink.push_interleaved(data, channels);
+            },
+            move |err| {
+                debug_eprintln!(debug_enabled, "Audio stream error: {}", err);
+            },
+            None,
+        )?;
+
+        stream.play()?;
+
+        // Store the stream to keep it alive during recording
+        {
+            let mut stream_guard = self.audio_stream.lock().unwrap();
+            *stream_guard = Some(stream);
+        }
+
+        Ok(sample_rate)
+    }
+
+    fn stop(&self) {
+        // Dropping the stream stops it
+        let mut stream_guard = self.audio_stream.lock().unwrap();
+        *stream_guard = None;
+    }
+}
+
+/// Stands in for `CpalSource` in builds without the `microphone` feature: recording from the
+/// system's input devices fails, while the other sources work as usual
+#[cfg(not(feature = "microphone"))]
+pub struct NoMicrophone;
+
+#[cfg(not(feature = "microphone"))]
+impl NoMicrophone {
+    pub fn new(_debug_enabled: bool) -> Self {
+        Self
+    }
+
+    pub fn list_input_devices() -> Result<Vec<InputDeviceInfo>> {
+        Err(VoiceError::MicrophoneUnsupported)
+    }
+}
+
+#[cfg(not(feature = "microphone"))]
+impl AudioSource for NoMicrophone {
+    fn start(&self, _device: Option<&str>, _sink: AudioSink) -> Result<u32> {
+        Err(VoiceError::MicrophoneUnsupported)
+    }
+
+    fn stop(&self) {}
+}
+
+/// The system's input devices, when this build can record from them
+#[cfg(feature = "microphone")]
+pub(crate) type SystemSource = CpalSource;
+#[cfg(not(feature = "microphone"))]
+pub(crate) type SystemSource = NoMicrophone;
+
+/// Plays an audio buffer back in real time, then keeps delivering silence like a microphone in a
+/// quiet room, so silence detection behaves as it would with live input
+pub struct ReplaySource {
+    audio: AudioBuffer,
+    running: Arc<AtomicBool>,
+    worker: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl ReplaySource {
+    pub fn new(audio: AudioBuffer) -> Self {
+        Self {
+            audio,
+            running: Arc::new(AtomicBool::new(false)),
+            worker: Mutex::new(None),
+        }
+    }
+
+    /// Replay an audio file in any format `AudioFileHandler` can load
+    pub fn from_file(path: &str) -> Result<Self> {
+        let audio = AudioFileHandler::new(DebugConfig::default()).load_audio_file(path)?;
+        Ok(Self::new(audio))
+    }
+}
+
+impl AudioSource for ReplaySource {
+    fn start(&self, _device: Option<&str>, mut sink: AudioSink) -> Result<u32> {
+        let sample_rate = self.audio.sample_rate;
+        if sample_rate == 0 {
+            return Err(VoiceError::AudioProcessing("Cannot replay audio with a sample rate of 0 Hz".to_string()));
+        }
+
+        self.stop();
+        self.running.store(true, Ordering::Relaxed);
+
+        let samples = self.audio.samples.clone();
+        let running = Arc::clone(&self.running);
+        let handle = std::thread::spawn(move || {
+            let started = Instant::now();
+            let mut position = 0usize;
+
+            while running.load(Ordering::Relaxed) && sink.is_recording() {
+                // Deliver everything that is due by now, padding with silence after the end
+                let due = (started.elapsed().as_secs_f64() * sample_rate as f64) as usize;
+                if due > position {
+                    let available = samples.get(position..due.min(samples.len())).unwrap_or_default();
+                    let mut chunk = available.to_vec();
+                    chunk.resize(due - position, 0.0);
+                    sink.push(&chunk);
+                    position = due;
+                }
+                std::thread::sleep(Duration::from_millis(REPLAY_TICK_MS));
+            }
+        });
+
+        *self.worker.lock().unwrap() = Some(handle);
+        Ok(sample_rate)
+    }
+
+    fn stop(&self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(handle) = self.worker.lock().unwrap().take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// A synthetic test signal built from tones, noise and silence, played back in real time.
+///
+/// ```
+/// use voice_to_text_mcp::audio_source::SignalSource;
+///
+/// // One second of "speech" followed by silence
+/// let source = SignalSource::new(16000).tone(440.0, 0.5, 1000).silence(500);
+/// ```
+pub struct SignalSource {
+    replay: ReplaySource,
+    noise_state: u32,
+}
+
+impl SignalSource {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            replay: ReplaySource::new(AudioBuffer::new(Vec::new(), sample_rate)),
+            noise_state: 0x2545_f491,
+        }
+    }
+
+    fn sample_count(&self, duration_ms: u64) -> usize {
+        (self.replay.audio.sample_rate as u64 * duration_ms / 1000) as usize
+    }
+
+    /// Append a sine tone
+    pub fn tone(mut self, frequency: f32, amplitude: f32, duration_ms: u64) -> Self {
+        let sample_rate = self.replay.audio.sample_rate as f32;
+        let count = self.sample_count(duration_ms);
+        self.replay.audio.samples.extend((0..count).map(|i| {
+            amplitude * (2.0 * std::f32::consts::PI * frequency * i as f32 / sample_rate).sin()
+        }));
+        self
+    }
+
+    /// Append deterministic white noise
+    pub fn noise(mut self, amplitude: f32, duration_ms: u64) -> Self {
+        let count = self.sample_count(duration_ms);
+        for _ in 0..count {
+            // xorshift32 keeps the signal reproducible without an RNG dependency
+            self.noise_state ^= self.noise_state << 13;
+            self.noise_state ^= self.noise_state >> 17;
+            self.noise_state ^= self.noise_state << 5;
+            let unit = self.noise_state as f32 / u32::MAX as f32;
+            self.replay.audio.samples.push(amplitude * (unit * 2.0 - 1.0));
+        }
+        self
+    }
+
+    /// Append silence
+    pub fn silence(mut self, duration_ms: u64) -> Self {
+        let count = self.sample_count(duration_ms);
+        self.replay.audio.samples.resize(self.replay.audio.samples.len() + count, 0.0);
+        self
+    }
+
+    /// The signal generated so far
+    pub fn audio(&self) -> &AudioBuffer {
+        &self.replay.audio
+    }
+}
+
+impl AudioSource for SignalSource {
+    fn start(&self, device: Option<&str>, sink: AudioSink) -> Result<u32> {
+        self.replay.start(device, sink)
+    }
+
+    fn stop(&self) {
+        self.replay.stop()
+    }
+}
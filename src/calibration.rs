@@ -0,0 +1,168 @@
+use crate::audio::AudioBuffer;
+use crate::config::*;
+use crate::error::{Result, VoiceError};
+use crate::vad::VadOptions;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Samples at or above this magnitude count as clipped
+const CLIPPING_LEVEL: f32 = 0.999;
+
+/// What a few seconds of room tone revealed about the input, and the voice activity
+/// detection settings recommended for it
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct CalibrationReport {
+    pub duration_seconds: f32,
+    pub sample_rate: u32,
+    /// Median level of the room tone in dBFS
+    pub noise_floor_db: f32,
+    /// Spread between loud and quiet moments of the room tone in dB
+    pub noise_variation_db: f32,
+    pub peak_db: f32,
+    /// Mean sample value; a microphone without a constant bias reads close to zero
+    pub dc_offset: f32,
+    pub clipped_samples: usize,
+    pub clipping_ratio: f32,
+    /// Problems with the input worth fixing before recording
+    pub warnings: Vec<String>,
+    pub recommended: VadOptions,
+    /// Where the recommended settings were saved, if they were
+    pub profile_path: Option<PathBuf>,
+}
+
+/// Measure recorded room tone and recommend VAD settings, keeping the mode of `current`
+pub fn analyze_room_tone(audio: &AudioBuffer, current: &VadOptions) -> Result<CalibrationReport> {
+    let duration = audio.duration_seconds();
+    if duration < MIN_AUDIO_DURATION {
+        return Err(VoiceError::AudioTooShort { duration });
+    }
+
+    let samples = &audio.samples;
+    let dc_offset = samples.iter().sum::<f32>() / samples.len() as f32;
+    let peak = samples.iter().fold(0.0f32, |peak, &s| peak.max(s.abs()));
+    let clipped_samples = samples.iter().filter(|s| s.abs() >= CLIPPING_LEVEL).count();
+    let clipping_ratio = clipped_samples as f32 / samples.len() as f32;
+
+    // Frame levels with the DC offset removed, as the detector measures them
+    let frame_len = samples_for_duration_ms(audio.sample_rate, VAD_FRAME_MS).max(1);
+    let mut levels: Vec<f32> = samples
+        .chunks_exact(frame_len)
+        .map(|frame| {
+            let mean_square = frame.iter().map(|s| (s - dc_offset).powi(2)).sum::<f32>() / frame_len as f32;
+            to_db(mean_square.sqrt())
+        })
+        .collect();
+    levels.sort_by(|a, b| a.total_cmp(b));
+    let percentile = |p: usize| levels[(levels.len() - 1) * p / 100];
+    let noise_floor_db = percentile(50);
+    let noise_variation_db = percentile(90) - percentile(10);
+
+    // A fluctuating room needs more headroom before a sound counts as speech
+    let onset_db = DEFAULT_VAD_ONSET_DB.max(noise_variation_db + 6.0);
+    let offset_db = onset_db - (DEFAULT_VAD_ONSET_DB - DEFAULT_VAD_OFFSET_DB);
+    let min_speech_level_db = VAD_MIN_SPEECH_LEVEL_DB.max(noise_floor_db + offset_db);
+    let recommended = VadOptions {
+        initial_noise_floor_db: noise_floor_db,
+        // The measured floor replaces the one heard at the start of each recording
+        seed_ms: 0,
+        min_speech_level_db,
+        threshold: 10f32.powf((noise_floor_db + onset_db) / 20.0),
+        onset_db,
+        offset_db,
+        ..current.clone()
+    };
+
+    let mut warnings = Vec::new();
+    if to_db(peak) < -90.0 {
+        warnings.push("No signal: check that the microphone is connected and not muted".to_string());
+    }
+    if clipping_ratio > 0.001 {
+        warnings.push(format!("{} samples clipped: lower the input gain", clipped_samples));
+    }
+    if dc_offset.abs() > 0.01 {
+        warnings.push(format!("DC offset of {:.3}: the microphone or its driver adds a constant bias", dc_offset));
+    }
+    if noise_floor_db > -35.0 {
+        warnings.push(format!("Loud background ({:.0} dBFS): speech must be well above it to be detected", noise_floor_db));
+    }
+    if noise_variation_db > 12.0 {
+        warnings.push(format!(
+            "The background varied by {:.0} dB: calibrate again without speech or sudden noises", noise_variation_db
+        ));
+    }
+
+    Ok(CalibrationReport {
+        duration_seconds: duration,
+        sample_rate: audio.sample_rate,
+        noise_floor_db,
+        noise_variation_db,
+        peak_db: to_db(peak),
+        dc_offset,
+        clipped_samples,
+        clipping_ratio,
+        warnings,
+        recommended,
+        profile_path: None,
+    })
+}
+
+fn to_db(amplitude: f32) -> f32 {
+    (20.0 * amplitude.max(1e-5).log10()).max(VAD_MIN_LEVEL_DB)
+}
+
+/// Calibrated settings saved between runs
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CalibrationProfile {
+    /// When the calibration ran (RFC 3339)
+    pub created_at: String,
+    /// Input device the room tone was recorded from; `None` for the default device
+    pub device: Option<String>,
+    pub noise_floor_db: f32,
+    pub vad: VadOptions,
+}
+
+impl CalibrationProfile {
+    pub fn from_report(report: &CalibrationReport, device: Option<&str>) -> Self {
+        Self {
+            created_at: Utc::now().to_rfc3339(),
+            device: device.map(str::to_string),
+            noise_floor_db: report.noise_floor_db,
+            vad: report.recommended.clone(),
+        }
+    }
+
+    /// `$VOICE_PROFILE`, or `voice-to-text-mcp/profile.json` in the user's configuration directory
+    pub fn default_path() -> Option<PathBuf> {
+        if let Some(path) = std::env::var_os("VOICE_PROFILE") {
+            return Some(PathBuf::from(path));
+        }
+
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(config_dir.join("voice-to-text-mcp").join("profile.json"))
+    }
+
+    /// Load a saved profile; a missing file is not an error
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        let json = match std::fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|e| VoiceError::Profile(format!("{}: {}", path.display(), e)))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(|e| VoiceError::Profile(e.to_string()))?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+}
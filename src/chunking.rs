@@ -0,0 +1,210 @@
+use crate::audio::{AudioBuffer, AudioProcessor};
+use crate::config::*;
+use crate::error::{Result, VoiceError};
+use crate::recording::RecordedAudio;
+use crate::transcription::{Segment, Transcription};
+
+/// Longest run of repeated words looked for where two chunks meet
+const MAX_SEAM_WORDS: usize = 12;
+
+/// How audio longer than one Whisper pass is split up
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChunkingOptions {
+    /// Maximum length of one chunk
+    pub chunk_ms: u64,
+    /// Audio shared by neighbouring chunks
+    pub overlap_ms: u64,
+    /// The cut is placed at the quietest point within this distance of the chunk end
+    pub search_ms: u64,
+}
+
+impl Default for ChunkingOptions {
+    fn default() -> Self {
+        Self {
+            chunk_ms: DEFAULT_CHUNK_MS,
+            overlap_ms: DEFAULT_CHUNK_OVERLAP_MS,
+            search_ms: DEFAULT_CHUNK_SEARCH_MS,
+        }
+    }
+}
+
+impl ChunkingOptions {
+    pub fn validate(&self) -> Result<()> {
+        if self.chunk_ms <= self.overlap_ms + self.search_ms {
+            return Err(VoiceError::InvalidOptions(format!(
+                "chunk length ({}ms) must be longer than the overlap ({}ms) plus the cut search range ({}ms)",
+                self.chunk_ms, self.overlap_ms, self.search_ms
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Progress of a chunked transcription, reported after every chunk
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChunkProgress {
+    /// Number of chunks transcribed so far
+    pub chunk: usize,
+    pub processed_seconds: f32,
+    pub total_seconds: f32,
+    /// Segments added by this chunk, on the timeline of the whole recording
+    pub segments: Vec<Segment>,
+}
+
+impl ChunkProgress {
+    pub fn percent(&self) -> f32 {
+        if self.total_seconds <= 0.0 {
+            return 100.0;
+        }
+        (self.processed_seconds / self.total_seconds * 100.0).min(100.0)
+    }
+}
+
+/// Transcribe `recording` chunk by chunk with `transcribe`, reading one chunk at a time.
+///
+/// Each chunk ends at the quietest point near its maximum length and the next one starts
+/// `overlap_ms` earlier. A segment belongs to the chunk that contains its midpoint, counting
+/// the middle of the overlap as the border, and words repeated across the border are dropped.
+pub(crate) fn transcribe_chunked(
+    recording: &RecordedAudio,
+    chunking: &ChunkingOptions,
+    progress: &mut dyn FnMut(&ChunkProgress),
+    mut transcribe: impl FnMut(&AudioBuffer) -> Result<Transcription>,
+) -> Result<Transcription> {
+    chunking.validate()?;
+
+    let sample_rate = recording.sample_rate();
+    if sample_rate == 0 {
+        return Err(VoiceError::AudioProcessing("Audio has a sample rate of 0Hz".to_string()));
+    }
+
+    let processor = AudioProcessor::new(false);
+    let total = recording.len();
+    let total_seconds = recording.duration_seconds();
+    let chunk_len = samples_for_duration_ms(sample_rate, chunking.chunk_ms);
+    let overlap = samples_for_duration_ms(sample_rate, chunking.overlap_ms);
+    let search = samples_for_duration_ms(sample_rate, chunking.search_ms);
+    let seconds = |samples: usize| samples as f32 / sample_rate as f32;
+
+    let mut segments: Vec<Segment> = Vec::new();
+    let mut language = None;
+    let mut start = 0;
+    let mut owned_from = 0.0;
+    let mut chunk = 0;
+
+    loop {
+        let mut audio = recording.read(start, chunk_len)?;
+        let (cut, next_start) = if start + chunk_len >= total {
+            (total, None)
+        } else {
+            let search_from = chunk_len - search;
+            let cut = start + search_from + processor.quietest_point(&audio.samples[search_from..], sample_rate);
+            (cut, Some(cut - overlap))
+        };
+        audio.samples.truncate(cut - start);
+
+        // The border with the next chunk is the middle of the audio both of them see
+        let owned_to = next_start.map_or(f32::INFINITY, |next| seconds(next + cut) / 2.0);
+        let offset = seconds(start);
+
+        let chunk_segments = match transcribe(&audio) {
+            Ok(transcription) => {
+                if language.is_none() {
+                    language = transcription.language;
+                }
+                transcription.segments
+            }
+            // A silent stretch of a long recording is not an error
+            Err(VoiceError::AudioTooShort { .. }) | Err(VoiceError::AudioTooQuiet { .. }) => Vec::new(),
+            Err(e) => return Err(e),
+        };
+
+        let owned = chunk_segments
+            .iter()
+            .map(|segment| segment.shifted(offset))
+            .filter(|segment| {
+                let midpoint = (segment.start + segment.end) / 2.0;
+                midpoint >= owned_from && midpoint < owned_to
+            })
+            .collect();
+        let added = stitch_segments(&mut segments, owned);
+
+        chunk += 1;
+        progress(&ChunkProgress {
+            chunk,
+            processed_seconds: seconds(cut),
+            total_seconds,
+            segments: segments[added..].to_vec(),
+        });
+
+        match next_start {
+            Some(next) => {
+                start = next;
+                owned_from = owned_to;
+            }
+            None => break,
+        }
+    }
+
+    let mut transcription = Transcription::from_segments(segments, language, total_seconds);
+    if transcription.text.is_empty() {
+        transcription.text = "No speech detected in audio".to_string();
+    }
+    Ok(transcription)
+}
+
+/// Append the next chunk's segments, dropping words at their start that repeat the end of the
+/// transcript so far. Returns the index of the first appended segment.
+pub(crate) fn stitch_segments(segments: &mut Vec<Segment>, next: Vec<Segment>) -> usize {
+    let first_added = segments.len();
+    let mut at_seam = true;
+
+    for mut segment in next {
+        if at_seam {
+            if let Some(previous) = segments.last() {
+                remove_repeated_words(&previous.text, &mut segment);
+                // Whisper starts segments with a space, but a chunk's first one sometimes lacks it
+                if !previous.text.ends_with(char::is_whitespace) && !segment.text.starts_with(char::is_whitespace) {
+                    segment.text.insert(0, ' ');
+                }
+            }
+            if segment.text.trim().is_empty() {
+                continue;
+            }
+            at_seam = false;
+        }
+        segments.push(segment);
+    }
+
+    first_added
+}
+
+/// Remove the longest run of leading words that repeats the end of `previous`; a single word
+/// only counts when it is the whole segment, since short words legitimately repeat
+fn remove_repeated_words(previous: &str, segment: &mut Segment) {
+    let previous_words: Vec<String> = previous.split_whitespace().map(normalize_word).collect();
+    let words: Vec<&str> = segment.text.split_whitespace().collect();
+    let normalized: Vec<String> = words.iter().map(|w| normalize_word(w)).collect();
+
+    let longest = MAX_SEAM_WORDS.min(previous_words.len()).min(words.len());
+    let repeated = (1..=longest).rev().find(|&count| {
+        (count >= 2 || count == words.len())
+            && previous_words[previous_words.len() - count..] == normalized[..count]
+            && normalized[..count].iter().all(|w| !w.is_empty())
+    });
+
+    let Some(count) = repeated else { return };
+    let remaining = words[count..].join(" ");
+    segment.text = if remaining.is_empty() { String::new() } else { format!(" {}", remaining) };
+    if segment.words.len() >= count {
+        segment.words.drain(..count);
+        if let Some(first) = segment.words.first() {
+            segment.start = first.start;
+        }
+    }
+}
+
+/// Lowercase a word and strip punctuation so "Friday." matches "friday"
+fn normalize_word(word: &str) -> String {
+    word.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
@@ -1,3 +1,4 @@
+use crate::vad::VadOptions;
 use std::path::PathBuf;
 
 // Audio processing constants
@@ -7,17 +8,97 @@ pub const SILENCE_THRESHOLD: f32 = 0.01;
 pub const MIN_AUDIO_DURATION: f32 = 0.5;
 pub const MIN_AUDIO_AMPLITUDE: f32 = 0.001;
 pub const CHECK_INTERVAL_MS: u64 = 100;
+pub const MAX_PENDING_UTTERANCES: usize = 8;
 pub const RECENT_SAMPLES_DURATION_MS: u64 = 100;
 
+// Capture ring buffer: ~5s at 48kHz, drained every few milliseconds
+pub const CAPTURE_RING_CAPACITY: usize = 1 << 18;
+pub const CAPTURE_DRAIN_INTERVAL_MS: u64 = 5;
+
+// Recordings keep this much audio in memory (~23 minutes at 48kHz) and spill the rest to disk
+pub const DEFAULT_MAX_MEMORY_MB: usize = 256;
+
+// Audio longer than one chunk is transcribed in chunks cut at the quietest point near the
+// chunk end, overlapping the next chunk slightly so no word is lost at the cut
+pub const DEFAULT_CHUNK_MS: u64 = 30000;
+pub const DEFAULT_CHUNK_OVERLAP_MS: u64 = 1000;
+pub const DEFAULT_CHUNK_SEARCH_MS: u64 = 5000;
+
+// Voice activity detection: levels are in dBFS, measured over short frames
+pub const VAD_FRAME_MS: u64 = 20;
+pub const VAD_NOISE_WINDOW_MS: u64 = 5000;
+pub const VAD_NOISE_RISE_DB_PER_SECOND: f32 = 10.0;
+pub const VAD_INITIAL_NOISE_FLOOR_DB: f32 = -50.0;
+pub const VAD_MIN_LEVEL_DB: f32 = -100.0;
+pub const VAD_MIN_SPEECH_LEVEL_DB: f32 = -65.0;
+pub const DEFAULT_VAD_ONSET_DB: f32 = 10.0;
+pub const DEFAULT_VAD_OFFSET_DB: f32 = 5.0;
+pub const DEFAULT_VAD_MIN_SPEECH_MS: u64 = 100;
+pub const DEFAULT_VAD_HANGOVER_MS: u64 = 200;
+pub const DEFAULT_VAD_SEED_MS: u64 = 200;
+
+// Room tone recorded by `calibrate`
+pub const DEFAULT_CALIBRATION_MS: u64 = 3000;
+
+// Transcriptions run at once, each holding its own Whisper state (and its memory)
+pub const DEFAULT_TRANSCRIPTION_WORKERS: usize = 1;
+
+// Background transcription jobs: how many may wait or run, and how many finished ones are kept
+pub const DEFAULT_MAX_PENDING_JOBS: usize = 16;
+pub const DEFAULT_MAX_FINISHED_JOBS: usize = 100;
+
+// Whisper decoding constants
+pub const NO_SPEECH_THRESHOLD: f32 = 0.6;
+
 // Default timeout values
 pub const DEFAULT_TIMEOUT_MS: u64 = 30000;
 pub const DEFAULT_SILENCE_TIMEOUT_MS: u64 = 2000;
+pub const DEFAULT_ONSET_TIMEOUT_MS: u64 = 5000;
+pub const DEFAULT_PRE_ROLL_MS: u64 = 300;
+
+// Streaming transcription defaults
+pub const DEFAULT_STREAM_INTERVAL_MS: u64 = 1000;
+pub const DEFAULT_STREAM_WINDOW_MS: u64 = 15000;
+
+// How often a recording reports its level and voice activity to progress listeners
+pub const RECORDING_PROGRESS_INTERVAL_MS: u64 = 500;
 
 // Audio buffer calculation helpers
 pub const fn samples_for_duration_ms(sample_rate: u32, duration_ms: u64) -> usize {
     ((sample_rate as u64 * duration_ms) / 1000) as usize
 }
 
+/// How a microphone recording is started and when it stops
+#[derive(Clone, Debug, PartialEq)]
+pub struct ListenOptions {
+    /// Maximum recording duration
+    pub timeout_ms: u64,
+    /// Trailing silence that ends the recording when `auto_stop` is set
+    pub silence_timeout_ms: u64,
+    /// How long to wait for speech to begin when `auto_stop` is set
+    pub onset_timeout_ms: u64,
+    /// Audio kept before the detected start of speech; anything earlier is trimmed
+    pub pre_roll_ms: u64,
+    pub auto_stop: bool,
+    /// Input device name (or part of it); `None` uses the service's device or the system default
+    pub device: Option<String>,
+    /// Voice activity detection used by `auto_stop`; `None` uses the service's settings
+    pub vad: Option<VadOptions>,
+}
+
+impl Default for ListenOptions {
+    fn default() -> Self {
+        Self {
+            timeout_ms: DEFAULT_TIMEOUT_MS,
+            silence_timeout_ms: DEFAULT_SILENCE_TIMEOUT_MS,
+            onset_timeout_ms: DEFAULT_ONSET_TIMEOUT_MS,
+            pre_roll_ms: DEFAULT_PRE_ROLL_MS,
+            auto_stop: true,
+            device: None,
+            vad: None,
+        }
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct DebugConfig {
@@ -0,0 +1,161 @@
+use crate::config::*;
+use crate::transcription::{Segment, Transcription};
+use crate::vad::{VadOptions, VoiceActivityDetector};
+use serde::Serialize;
+use std::ops::Range;
+
+/// One stretch of speech heard in continuous mode and its transcription
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Utterance {
+    /// Position among the session's utterances, counting from 0
+    pub index: usize,
+    /// Start time in seconds from the beginning of the session
+    pub start: f32,
+    /// End time in seconds from the beginning of the session
+    pub end: f32,
+    pub text: String,
+    pub language: Option<String>,
+    /// Segments timed from the beginning of the session
+    pub segments: Vec<Segment>,
+}
+
+impl Utterance {
+    /// The transcription of the audio between samples `range` of a session
+    pub(crate) fn new(index: usize, range: Range<usize>, sample_rate: u32, transcription: Transcription) -> Self {
+        let (start, end) = seconds(&range, sample_rate);
+        Self {
+            index,
+            start,
+            end,
+            text: transcription.text,
+            language: transcription.language,
+            segments: transcription.segments.iter().map(|s| s.shifted(start)).collect(),
+        }
+    }
+}
+
+/// What continuous listening sends to the caller; serialized as the utterance or the error alone
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum ContinuousEvent {
+    Utterance(Utterance),
+    /// Speech that was heard but not transcribed, because transcription failed or fell too far behind
+    Error {
+        /// Start time in seconds from the beginning of the session
+        start: f32,
+        /// End time in seconds from the beginning of the session
+        end: f32,
+        error: String,
+    },
+}
+
+impl ContinuousEvent {
+    /// The failure to transcribe the audio between samples `range` of a session
+    pub(crate) fn error(range: &Range<usize>, sample_rate: u32, error: impl ToString) -> Self {
+        let (start, end) = seconds(range, sample_rate);
+        ContinuousEvent::Error { start, end, error: error.to_string() }
+    }
+}
+
+/// Start and end of samples `range` in seconds
+fn seconds(range: &Range<usize>, sample_rate: u32) -> (f32, f32) {
+    let seconds = |sample: usize| sample as f32 / sample_rate.max(1) as f32;
+    (seconds(range.start), seconds(range.end))
+}
+
+/// Splits a continuous capture into utterances with voice activity detection.
+///
+/// An utterance starts `pre_roll_ms` before speech is detected and ends `pre_roll_ms` after the
+/// speaker falls silent, once the silence has lasted `silence_timeout_ms`. Speech that runs on
+/// for longer than `timeout_ms` is cut there so it is transcribed without waiting for a pause.
+pub(crate) struct UtteranceSegmenter {
+    vad: VoiceActivityDetector,
+    frame_len: usize,
+    pre_roll: usize,
+    end_silence: usize,
+    max_len: usize,
+    /// How far before the current position a late-confirmed onset can lie
+    lookback: usize,
+    /// Samples processed so far
+    position: usize,
+    /// End of the last utterance; the next one cannot start before it
+    committed: usize,
+    /// Start of the utterance in progress
+    start: Option<usize>,
+    last_speech: usize,
+}
+
+impl UtteranceSegmenter {
+    pub fn new(listen: &ListenOptions, vad: VadOptions, sample_rate: u32) -> Self {
+        let samples = |ms: u64| samples_for_duration_ms(sample_rate, ms);
+        let pre_roll = samples(listen.pre_roll_ms);
+        Self {
+            frame_len: samples(VAD_FRAME_MS).max(1),
+            pre_roll,
+            end_silence: samples(listen.silence_timeout_ms).max(1),
+            max_len: samples(listen.timeout_ms).max(1),
+            lookback: pre_roll + samples(vad.min_speech_ms + VAD_FRAME_MS),
+            vad: VoiceActivityDetector::new(vad, sample_rate),
+            position: 0,
+            committed: 0,
+            start: None,
+            last_speech: 0,
+        }
+    }
+
+    /// Samples processed so far; the next call to `push` continues from here
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Earliest sample a future utterance can include; audio before it is no longer needed
+    pub fn retain_from(&self) -> usize {
+        self.start.unwrap_or_else(|| self.position.saturating_sub(self.lookback))
+    }
+
+    /// Feed newly captured samples and return the utterances they completed
+    pub fn push(&mut self, samples: &[f32]) -> Vec<Range<usize>> {
+        // Frame by frame, so the result does not depend on how the capture is polled
+        samples.chunks(self.frame_len).filter_map(|frame| self.step(frame)).collect()
+    }
+
+    fn step(&mut self, frame: &[f32]) -> Option<Range<usize>> {
+        let speaking = self.vad.process(frame);
+        self.position += frame.len();
+
+        if speaking {
+            self.last_speech = self.position;
+            if self.start.is_none() {
+                let onset = self.vad.last_onset().unwrap_or(self.position);
+                self.start = Some(onset.saturating_sub(self.pre_roll).max(self.committed));
+            }
+        }
+
+        let start = self.start?;
+        if !speaking && self.position - self.last_speech >= self.end_silence {
+            let end = (self.last_speech + self.pre_roll).min(self.position);
+            Some(self.cut(start, end))
+        } else if self.position - start >= self.max_len {
+            let range = self.cut(start, self.position);
+            if speaking {
+                self.start = Some(self.position);
+            }
+            Some(range)
+        } else {
+            None
+        }
+    }
+
+    fn cut(&mut self, start: usize, end: usize) -> Range<usize> {
+        self.start = None;
+        self.committed = end;
+        start..end
+    }
+
+    /// The utterance in progress when listening stops, if any
+    pub fn finish(&mut self) -> Option<Range<usize>> {
+        let start = self.start?;
+        let end = (self.last_speech + self.pre_roll).min(self.position);
+        Some(self.cut(start, end))
+    }
+}
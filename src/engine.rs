@@ -0,0 +1,205 @@
+use crate::audio::AudioBuffer;
+use crate::error::{Result, VoiceError};
+use crate::transcription::{Segment, Transcription};
+use crate::whisper::TranscriptionOptions;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+/// A speech-to-text backend for `VoiceToTextService`
+pub trait TranscriptionEngine: Send + Sync {
+    /// Whether the engine can transcribe; without a model the service returns a placeholder transcript
+    fn has_model(&self) -> bool;
+
+    /// Transcribe mono audio at any sample rate.
+    ///
+    /// This blocks until the transcription is done; the service calls it off the async runtime,
+    /// from several threads at once when it has more than one transcription worker.
+    fn transcribe(&self, audio: &AudioBuffer, options: &TranscriptionOptions) -> Result<Transcription>;
+
+    /// Like `transcribe`, but gives up with `VoiceError::Cancelled` once `cancel` is cancelled.
+    ///
+    /// The default only checks before starting; engines that can stop part-way through override it.
+    fn transcribe_cancellable(&self, audio: &AudioBuffer, options: &TranscriptionOptions, cancel: &CancellationToken) -> Result<Transcription> {
+        if cancel.is_cancelled() {
+            return Err(VoiceError::Cancelled);
+        }
+        self.transcribe(audio, options)
+    }
+
+    /// Like `transcribe_cancellable`, calling `progress` with the percentage of the audio transcribed so far.
+    ///
+    /// The default reports 100% once the transcription is done.
+    fn transcribe_with_progress(
+        &self,
+        audio: &AudioBuffer,
+        options: &TranscriptionOptions,
+        cancel: &CancellationToken,
+        progress: &mut dyn FnMut(f32),
+    ) -> Result<Transcription> {
+        let transcription = self.transcribe_cancellable(audio, options, cancel)?;
+        progress(100.0);
+        Ok(transcription)
+    }
+
+    /// Called with the service's number of transcription workers, the most transcriptions it runs
+    /// at once; an engine holding resources per transcription should keep no more than that.
+    fn set_workers(&self, _workers: usize) {}
+}
+
+/// What the service passed to a `MockEngine`
+#[derive(Clone, Debug, PartialEq)]
+pub struct MockCall {
+    pub sample_count: usize,
+    pub sample_rate: u32,
+    pub options: TranscriptionOptions,
+}
+
+enum MockResponse {
+    Text(String),
+    Transcription(Transcription),
+    Error(VoiceError),
+}
+
+#[derive(Default)]
+struct MockState {
+    responses: Mutex<VecDeque<MockResponse>>,
+    calls: Mutex<Vec<MockCall>>,
+    latency: Mutex<Duration>,
+    workers: Mutex<Option<usize>>,
+    without_model: bool,
+}
+
+/// A scriptable engine for testing the service and MCP layers without a Whisper model.
+///
+/// Scripted responses are returned in order, one per call; once they run out every call
+/// returns "Mock transcription of N samples". Clones share the script and the call log.
+///
+/// ```
+/// use voice_to_text_mcp::{MockEngine, VoiceError};
+///
+/// let engine = MockEngine::new()
+///     .then_text("Hello world.")
+///     .then_error(VoiceError::WhisperTranscription("decoder failed".to_string()));
+/// ```
+#[derive(Clone, Default)]
+pub struct MockEngine {
+    state: Arc<MockState>,
+}
+
+impl MockEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// An engine that reports no loaded model, like `WhisperTranscriber::new`
+    pub fn without_model() -> Self {
+        Self {
+            state: Arc::new(MockState { without_model: true, ..Default::default() }),
+        }
+    }
+
+    /// Block every call for `latency`, reporting progress as it passes, as a real model would;
+    /// a cancelled call returns early
+    pub fn with_latency(self, latency: Duration) -> Self {
+        *self.state.latency.lock().unwrap() = latency;
+        self
+    }
+
+    /// Answer the next unanswered call with one segment spanning the audio; like Whisper's, the
+    /// segment text starts with a space
+    pub fn then_text(self, text: &str) -> Self {
+        self.push(MockResponse::Text(text.to_string()))
+    }
+
+    /// Answer the next unanswered call with `transcription` as is
+    pub fn then_transcription(self, transcription: Transcription) -> Self {
+        self.push(MockResponse::Transcription(transcription))
+    }
+
+    /// Fail the next unanswered call with `error`
+    pub fn then_error(self, error: VoiceError) -> Self {
+        self.push(MockResponse::Error(error))
+    }
+
+    fn push(self, response: MockResponse) -> Self {
+        self.state.responses.lock().unwrap().push_back(response);
+        self
+    }
+
+    /// Every call made so far, oldest first
+    pub fn calls(&self) -> Vec<MockCall> {
+        self.state.calls.lock().unwrap().clone()
+    }
+
+    /// The worker count last passed to `set_workers`
+    pub fn workers(&self) -> Option<usize> {
+        *self.state.workers.lock().unwrap()
+    }
+
+    /// Number of scripted responses not used yet
+    pub fn remaining_responses(&self) -> usize {
+        self.state.responses.lock().unwrap().len()
+    }
+}
+
+impl TranscriptionEngine for MockEngine {
+    fn has_model(&self) -> bool {
+        !self.state.without_model
+    }
+
+    fn transcribe(&self, audio: &AudioBuffer, options: &TranscriptionOptions) -> Result<Transcription> {
+        self.transcribe_cancellable(audio, options, &CancellationToken::new())
+    }
+
+    fn transcribe_cancellable(&self, audio: &AudioBuffer, options: &TranscriptionOptions, cancel: &CancellationToken) -> Result<Transcription> {
+        self.transcribe_with_progress(audio, options, cancel, &mut |_| {})
+    }
+
+    fn transcribe_with_progress(
+        &self,
+        audio: &AudioBuffer,
+        options: &TranscriptionOptions,
+        cancel: &CancellationToken,
+        progress: &mut dyn FnMut(f32),
+    ) -> Result<Transcription> {
+        self.state.calls.lock().unwrap().push(MockCall {
+            sample_count: audio.len(),
+            sample_rate: audio.sample_rate,
+            options: options.clone(),
+        });
+
+        let latency = *self.state.latency.lock().unwrap();
+        let finished = Instant::now() + latency;
+        loop {
+            if cancel.is_cancelled() {
+                return Err(VoiceError::Cancelled);
+            }
+            let remaining = finished.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+            progress(100.0 - remaining.as_secs_f32() / latency.as_secs_f32() * 100.0);
+            std::thread::sleep(remaining.min(Duration::from_millis(10)));
+        }
+        progress(100.0);
+
+        let duration = audio.duration_seconds();
+        let text = match self.state.responses.lock().unwrap().pop_front() {
+            Some(MockResponse::Transcription(transcription)) => return Ok(transcription),
+            Some(MockResponse::Error(error)) => return Err(error),
+            Some(MockResponse::Text(text)) => text,
+            None => format!("Mock transcription of {} samples", audio.len()),
+        };
+
+        let text = if text.starts_with(char::is_whitespace) { text } else { format!(" {}", text) };
+        let language = options.language().unwrap_or("en").to_string();
+        let segment = Segment { start: 0.0, end: duration, text, no_speech_prob: 0.0, words: vec![] };
+        Ok(Transcription::from_segments(vec![segment], Some(language), duration))
+    }
+
+    fn set_workers(&self, workers: usize) {
+        *self.state.workers.lock().unwrap() = Some(workers);
+    }
+}
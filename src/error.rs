@@ -8,6 +8,12 @@ pub enum VoiceError {
     #[error("No input device found")]
     NoInputDevice,
     
+    #[error("Input device not found: {0}")]
+    InputDeviceNotFound(String),
+    
+    #[error("Microphone capture is not available: this build does not include the `microphone` feature")]
+    MicrophoneUnsupported,
+    
     #[error("Audio stream error: {0}")]
     AudioStream(String),
     
@@ -20,6 +26,12 @@ pub enum VoiceError {
     #[error("Whisper transcription failed: {0}")]
     WhisperTranscription(String),
     
+    #[error("Unsupported transcription language: {0}")]
+    UnsupportedLanguage(String),
+    
+    #[error("Invalid transcription options: {0}")]
+    InvalidOptions(String),
+    
     #[error("Audio too short: {duration:.2}s (need at least 0.5s)")]
     AudioTooShort { duration: f32 },
     
@@ -29,6 +41,15 @@ pub enum VoiceError {
     #[error("WAV file error: {0}")]
     WavFile(String),
     
+    #[error("Unsupported audio format: {0}")]
+    UnsupportedAudioFormat(String),
+    
+    #[error("Unsupported audio codec: {0}")]
+    UnsupportedCodec(String),
+    
+    #[error("Audio decoding failed: {0}")]
+    AudioDecode(String),
+    
     #[error("Debug directory creation failed: {0}")]
     DebugDirectory(String),
     
@@ -41,6 +62,9 @@ pub enum VoiceError {
     #[error("Not currently recording")]
     NotRecording,
     
+    #[error("Captured audio was lost: {0}")]
+    CaptureLost(String),
+    
     #[error("Keyboard control initialization failed: {0}")]
     KeyboardControl(String),
     
@@ -52,6 +76,18 @@ pub enum VoiceError {
     
     #[error("Audio processing error: {0}")]
     AudioProcessing(String),
+    
+    #[error("Calibration profile error: {0}")]
+    Profile(String),
+    
+    #[error("Job queue is full: {limit} jobs are already waiting or running")]
+    JobQueueFull { limit: usize },
+    
+    #[error("Job not found: {0}")]
+    JobNotFound(u64),
+    
+    #[error("Operation cancelled")]
+    Cancelled,
 }
 
 impl From<anyhow::Error> for VoiceError {
@@ -60,18 +96,21 @@ impl From<anyhow::Error> for VoiceError {
     }
 }
 
+#[cfg(feature = "microphone")]
 impl From<cpal::BuildStreamError> for VoiceError {
     fn from(error: cpal::BuildStreamError) -> Self {
         VoiceError::AudioStream(error.to_string())
     }
 }
 
+#[cfg(feature = "microphone")]
 impl From<cpal::PlayStreamError> for VoiceError {
     fn from(error: cpal::PlayStreamError) -> Self {
         VoiceError::AudioStream(error.to_string())
     }
 }
 
+#[cfg(feature = "microphone")]
 impl From<cpal::DefaultStreamConfigError> for VoiceError {
     fn from(error: cpal::DefaultStreamConfigError) -> Self {
         VoiceError::AudioStream(error.to_string())
@@ -84,6 +123,17 @@ impl From<hound::Error> for VoiceError {
     }
 }
 
+impl From<symphonia::core::errors::Error> for VoiceError {
+    fn from(error: symphonia::core::errors::Error) -> Self {
+        use symphonia::core::errors::Error;
+        match error {
+            Error::IoError(e) => VoiceError::Io(e),
+            Error::Unsupported(what) => VoiceError::UnsupportedCodec(what.to_string()),
+            other => VoiceError::AudioDecode(other.to_string()),
+        }
+    }
+}
+
 impl From<whisper_rs::WhisperError> for VoiceError {
     fn from(error: whisper_rs::WhisperError) -> Self {
         VoiceError::WhisperTranscription(error.to_string())
@@ -0,0 +1,280 @@
+use crate::config::*;
+use crate::error::{Result, VoiceError};
+use crate::progress::ProgressEvent;
+use crate::scheduler::{Priority, PrioritySemaphore};
+use crate::transcription::{Segment, Transcription};
+use crate::whisper::TranscriptionOptions;
+use crate::VoiceToTextService;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+/// How many jobs a `JobQueue` accepts and remembers
+#[derive(Clone, Debug, PartialEq)]
+pub struct JobLimits {
+    /// Jobs queued or running at once; further submissions are refused until one finishes
+    pub max_pending: usize,
+    /// Finished jobs kept for `get`; the oldest are forgotten first
+    pub max_finished: usize,
+}
+
+impl Default for JobLimits {
+    fn default() -> Self {
+        Self {
+            max_pending: DEFAULT_MAX_PENDING_JOBS,
+            max_finished: DEFAULT_MAX_FINISHED_JOBS,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobState {
+    pub fn is_finished(self) -> bool {
+        matches!(self, JobState::Completed | JobState::Failed | JobState::Cancelled)
+    }
+}
+
+impl std::fmt::Display for JobState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            JobState::Queued => "queued",
+            JobState::Running => "running",
+            JobState::Completed => "completed",
+            JobState::Failed => "failed",
+            JobState::Cancelled => "cancelled",
+        })
+    }
+}
+
+/// Where a job stands, without its transcript
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct JobSummary {
+    pub id: u64,
+    pub file_path: String,
+    pub priority: Priority,
+    pub state: JobState,
+    /// Share of the audio transcribed so far, 0-100
+    pub progress_percent: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A job with the text transcribed so far
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct JobStatus {
+    #[serde(flatten)]
+    pub summary: JobSummary,
+    /// Segments finished so far while the job runs; empty once `result` is available
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub segments: Vec<Segment>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Transcription>,
+}
+
+struct Job {
+    status: JobStatus,
+    cancel: CancellationToken,
+    task: Option<JoinHandle<()>>,
+}
+
+#[derive(Default)]
+struct JobTable {
+    next_id: u64,
+    jobs: BTreeMap<u64, Job>,
+}
+
+struct JobQueueInner {
+    service: VoiceToTextService,
+    /// Jobs transcribing at once, one per transcription worker
+    runners: PrioritySemaphore,
+    limits: JobLimits,
+    table: Mutex<JobTable>,
+}
+
+/// Transcribes audio files in the background and keeps their results until they are collected.
+///
+/// A job waits until one of the service's transcription workers is free, highest priority
+/// first. Even then its chunks only run when no interactive call (such as a recording being
+/// transcribed) is waiting for a worker, so submitted jobs never hold up the microphone tools.
+#[derive(Clone)]
+pub struct JobQueue {
+    inner: Arc<JobQueueInner>,
+}
+
+impl JobQueue {
+    /// A queue running jobs on `service`, limited by its job limits and worker count
+    pub fn new(service: VoiceToTextService) -> Self {
+        Self {
+            inner: Arc::new(JobQueueInner {
+                runners: PrioritySemaphore::new(service.get_transcription_workers()),
+                limits: service.get_job_limits().clone(),
+                service,
+                table: Mutex::new(JobTable { next_id: 1, ..Default::default() }),
+            }),
+        }
+    }
+
+    /// Queue `file_path` for transcription and return the new job's ID at once
+    pub fn submit(&self, file_path: &str, options: TranscriptionOptions, priority: Priority) -> Result<u64> {
+        // Fail now rather than in the background when the file is obviously missing
+        std::fs::metadata(file_path)?;
+
+        let cancel = CancellationToken::new();
+        let id = {
+            let mut table = self.inner.table.lock().unwrap();
+            let pending = table.jobs.values().filter(|job| !job.status.summary.state.is_finished()).count();
+            if pending >= self.inner.limits.max_pending {
+                return Err(VoiceError::JobQueueFull { limit: self.inner.limits.max_pending });
+            }
+
+            let id = table.next_id;
+            table.next_id += 1;
+            let summary = JobSummary {
+                id,
+                file_path: file_path.to_string(),
+                priority,
+                state: JobState::Queued,
+                progress_percent: 0.0,
+                error: None,
+            };
+            let status = JobStatus { summary, segments: Vec::new(), result: None };
+            table.jobs.insert(id, Job { status, cancel: cancel.clone(), task: None });
+            id
+        };
+
+        let task = tokio::spawn(run_job(Arc::clone(&self.inner), id, file_path.to_string(), options, priority, cancel));
+        if let Some(job) = self.inner.table.lock().unwrap().jobs.get_mut(&id) {
+            job.task = Some(task);
+        }
+        Ok(id)
+    }
+
+    pub fn get(&self, id: u64) -> Result<JobStatus> {
+        let table = self.inner.table.lock().unwrap();
+        table.jobs.get(&id).map(|job| job.status.clone()).ok_or(VoiceError::JobNotFound(id))
+    }
+
+    /// Every job still known, oldest first
+    pub fn list(&self) -> Vec<JobSummary> {
+        let table = self.inner.table.lock().unwrap();
+        table.jobs.values().map(|job| job.status.summary.clone()).collect()
+    }
+
+    /// Stop a queued or running job; a finished job is left as it is
+    pub fn cancel(&self, id: u64) -> Result<JobSummary> {
+        let mut table = self.inner.table.lock().unwrap();
+        let job = table.jobs.get_mut(&id).ok_or(VoiceError::JobNotFound(id))?;
+        if !job.status.summary.state.is_finished() {
+            // Stops Whisper part-way through the chunk it is working on
+            job.cancel.cancel();
+            if let Some(task) = job.task.take() {
+                task.abort();
+            }
+            job.status.summary.state = JobState::Cancelled;
+            job.status.summary.error = Some(VoiceError::Cancelled.to_string());
+        }
+        Ok(job.status.summary.clone())
+    }
+}
+
+impl std::fmt::Debug for JobQueue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JobQueue")
+            .field("jobs", &self.inner.table.lock().unwrap().jobs.len())
+            .field("limits", &self.inner.limits)
+            .finish()
+    }
+}
+
+async fn run_job(
+    inner: Arc<JobQueueInner>,
+    id: u64,
+    file_path: String,
+    options: TranscriptionOptions,
+    priority: Priority,
+    cancel: CancellationToken,
+) {
+    let result = match inner.runners.acquire(priority).await {
+        Ok(_runner) => {
+            inner.update(id, |status| status.summary.state = JobState::Running);
+            // Whisper's own progress moves the percentage along within each chunk
+            let (events, mut progress_events) = mpsc::unbounded_channel();
+            let service = inner.service.for_job(priority, cancel).with_progress(events);
+            let chunks = Arc::clone(&inner);
+            let transcription = service.transcribe_audio_file_with_progress(&file_path, &options, move |chunk| {
+                chunks.update(id, |status| {
+                    status.summary.progress_percent = status.summary.progress_percent.max(chunk.percent());
+                    status.segments.extend(chunk.segments.iter().cloned());
+                });
+            });
+            tokio::pin!(transcription);
+            loop {
+                tokio::select! {
+                    result = &mut transcription => break result,
+                    Some(ProgressEvent::Transcribing { percent }) = progress_events.recv() => {
+                        inner.update(id, |status| {
+                            status.summary.progress_percent = status.summary.progress_percent.max(percent);
+                        });
+                    }
+                }
+            }
+        }
+        Err(e) => Err(e),
+    };
+    inner.finish(id, result);
+}
+
+impl JobQueueInner {
+    /// Change a job that is still queued or running
+    fn update(&self, id: u64, change: impl FnOnce(&mut JobStatus)) {
+        let mut table = self.table.lock().unwrap();
+        if let Some(job) = table.jobs.get_mut(&id) {
+            if !job.status.summary.state.is_finished() {
+                change(&mut job.status);
+            }
+        }
+    }
+
+    fn finish(&self, id: u64, result: Result<Transcription>) {
+        let mut table = self.table.lock().unwrap();
+        if let Some(job) = table.jobs.get_mut(&id) {
+            job.task = None;
+            let status = &mut job.status;
+            if !status.summary.state.is_finished() {
+                match result {
+                    Ok(transcription) => {
+                        status.summary.state = JobState::Completed;
+                        status.summary.progress_percent = 100.0;
+                        status.segments.clear();
+                        status.result = Some(transcription);
+                    }
+                    Err(e) => {
+                        status.summary.state = if matches!(e, VoiceError::Cancelled) { JobState::Cancelled } else { JobState::Failed };
+                        status.summary.error = Some(e.to_string());
+                    }
+                }
+            }
+        }
+
+        // Forget the oldest finished jobs beyond the limit
+        let finished: Vec<u64> = table.jobs.iter()
+            .filter(|(_, job)| job.status.summary.state.is_finished())
+            .map(|(&id, _)| id)
+            .collect();
+        for id in finished.iter().take(finished.len().saturating_sub(self.limits.max_finished)) {
+            table.jobs.remove(id);
+        }
+    }
+}
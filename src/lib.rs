@@ -1,3 +1,7 @@
+use serde::Serialize;
+use std::cell::Cell;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use tokio::time::{sleep, Duration, Instant};
 
@@ -7,24 +11,93 @@ pub mod config;
 pub mod platform;
 pub mod platform_compat;
 pub mod audio;
+pub mod audio_source;
+pub mod recording;
+pub mod resample;
+mod ring_buffer;
+#[cfg(feature = "opus")]
+mod opus;
 pub mod whisper;
+pub mod engine;
+pub mod chunking;
+pub mod vad;
+pub mod calibration;
+pub mod transcription;
+pub mod output_format;
+pub mod streaming;
+pub mod continuous;
+pub mod progress;
+pub mod scheduler;
+pub mod jobs;
 pub mod mcp_server;
+#[cfg(test)]
+#[path = "../tests/common/mod.rs"]
+mod test_common;
 
 // Re-export commonly used types
 pub use error::{Result, VoiceError};
-pub use config::{DebugConfig};
-pub use audio::{AudioCapture, AudioProcessor, AudioFileHandler};
-pub use whisper::WhisperTranscriber;
+pub use config::{DebugConfig, ListenOptions};
+pub use audio::{AudioBuffer, AudioCapture, AudioProcessor, AudioFileHandler, AudioFormat};
+pub use recording::RecordedAudio;
+pub use resample::{ResampleQuality, Resampler};
+pub use audio_source::{AudioSink, AudioSource, InputDeviceInfo, ReplaySource, SignalSource};
+#[cfg(feature = "microphone")]
+pub use audio_source::CpalSource;
+pub use whisper::{DecodingOptions, DecodingStrategy, TranscriptionOptions, WhisperTranscriber};
+pub use chunking::{ChunkProgress, ChunkingOptions};
+pub use vad::{VadMode, VadOptions, VoiceActivity, VoiceActivityDetector};
+pub use calibration::{CalibrationProfile, CalibrationReport};
+pub use engine::{MockCall, MockEngine, TranscriptionEngine};
+pub use transcription::{Segment, Transcription, Word};
+pub use output_format::OutputFormat;
+pub use streaming::{StreamEvent, StreamingOptions};
+pub use continuous::{ContinuousEvent, Utterance};
+pub use progress::ProgressEvent;
+pub use tokio_util::sync::CancellationToken;
+pub use scheduler::Priority;
+pub use jobs::{JobLimits, JobQueue, JobState, JobStatus, JobSummary};
 
 use config::*;
 use platform::debug_eprintln;
+use streaming::StreamState;
+use continuous::UtteranceSegmenter;
+use tokio::sync::mpsc::UnboundedSender;
+use scheduler::{PrioritySemaphore, SlotPermit};
+
+/// Snapshot of the microphone recording
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RecordingStatus {
+    pub recording: bool,
+    /// Seconds of audio captured so far (or in the last recording)
+    pub duration_seconds: f32,
+    pub sample_rate: u32,
+}
 
 #[derive(Clone)]
 pub struct VoiceToTextService {
     audio_capture: Arc<Mutex<AudioCapture>>,
     audio_file_handler: Arc<AudioFileHandler>,
-    whisper_transcriber: Arc<tokio::sync::Mutex<WhisperTranscriber>>,
+    engine: Arc<dyn TranscriptionEngine>,
+    /// One permit per transcription that may run at once on the blocking thread pool
+    transcription_slots: PrioritySemaphore,
+    transcription_workers: usize,
+    /// Where this clone's transcriptions queue for a worker; lowered for background jobs
+    priority: Priority,
+    /// Cancelling it stops this clone's recordings and transcriptions
+    cancel: CancellationToken,
+    /// Where this clone reports how far its recordings and transcriptions have got
+    progress: Option<UnboundedSender<ProgressEvent>>,
+    job_limits: JobLimits,
     debug_config: DebugConfig,
+    transcription_options: TranscriptionOptions,
+    chunking_options: ChunkingOptions,
+    /// Shared so a calibration updates every clone of the service
+    vad_options: Arc<Mutex<VadOptions>>,
+    /// Where `calibrate` saves its profile
+    profile_path: Option<PathBuf>,
+    input_device: Option<String>,
+    /// Set while `listen_continuous` runs; cleared by `stop_continuous`
+    continuous: Arc<AtomicBool>,
 }
 
 impl VoiceToTextService {
@@ -33,16 +106,8 @@ impl VoiceToTextService {
     }
 
     pub fn new_with_debug(debug_config: DebugConfig) -> Self {
-        let audio_capture = Arc::new(Mutex::new(AudioCapture::new(debug_config.enabled)));
-        let audio_file_handler = Arc::new(AudioFileHandler::new(debug_config.clone()));
-        let whisper_transcriber = Arc::new(tokio::sync::Mutex::new(WhisperTranscriber::new(debug_config.enabled)));
-
-        Self {
-            audio_capture,
-            audio_file_handler,
-            whisper_transcriber,
-            debug_config,
-        }
+        let engine = WhisperTranscriber::new(debug_config.enabled);
+        Self::new_with_engine(engine, debug_config)
     }
 
     pub fn new_with_model(model_path: &str) -> Result<Self> {
@@ -50,84 +115,399 @@ impl VoiceToTextService {
     }
 
     pub fn new_with_model_and_debug(model_path: &str, debug_config: DebugConfig) -> Result<Self> {
+        let engine = WhisperTranscriber::new_with_model(model_path, debug_config.enabled)?;
+        Ok(Self::new_with_engine(engine, debug_config))
+    }
+
+    /// Transcribe with `engine` instead of Whisper, e.g. a `MockEngine` in tests
+    pub fn new_with_engine(engine: impl TranscriptionEngine + 'static, debug_config: DebugConfig) -> Self {
         let audio_capture = Arc::new(Mutex::new(AudioCapture::new(debug_config.enabled)));
         let audio_file_handler = Arc::new(AudioFileHandler::new(debug_config.clone()));
-        let whisper_transcriber = Arc::new(tokio::sync::Mutex::new(WhisperTranscriber::new_with_model(model_path, debug_config.enabled)?));
 
-        Ok(Self {
+        Self {
             audio_capture,
             audio_file_handler,
-            whisper_transcriber,
+            engine: Arc::new(engine),
+            transcription_slots: PrioritySemaphore::new(DEFAULT_TRANSCRIPTION_WORKERS),
+            transcription_workers: DEFAULT_TRANSCRIPTION_WORKERS,
+            priority: Priority::Interactive,
+            cancel: CancellationToken::new(),
+            progress: None,
+            job_limits: JobLimits::default(),
             debug_config,
-        })
+            transcription_options: TranscriptionOptions::default(),
+            chunking_options: ChunkingOptions::default(),
+            vad_options: Arc::new(Mutex::new(VadOptions::default())),
+            profile_path: None,
+            input_device: None,
+            continuous: Arc::new(AtomicBool::new(false)),
+        }
     }
 
     pub async fn start_listening(&self) -> Result<String> {
-        {
-            let audio_capture = self.audio_capture.lock().unwrap();
-            audio_capture.start_capture()?;
-        }
+        self.start_capture(None)?;
         Ok("Started listening...".to_string())
     }
 
-    pub async fn start_listening_with_options(&self, timeout_ms: u64, silence_timeout_ms: u64, auto_stop: bool) -> Result<String> {
+    /// Start capturing from `device`, falling back to the service's input device
+    fn start_capture(&self, device: Option<&str>) -> Result<()> {
+        let device = device.or(self.input_device.as_deref());
+        let audio_capture = self.audio_capture.lock().unwrap();
+        audio_capture.start_capture_on_device(device)
+    }
+
+    /// Record until the timeout (or trailing silence when `auto_stop` is set) and transcribe the result
+    pub async fn start_listening_with_options(&self, listen: &ListenOptions, options: &TranscriptionOptions) -> Result<Transcription> {
         // Check if we have a Whisper model loaded
-        {
-            let whisper_transcriber = self.whisper_transcriber.lock().await;
-            if !whisper_transcriber.has_model() {
-                return Err(VoiceError::WhisperModelNotLoaded);
-            }
+        if !self.engine.has_model() {
+            return Err(VoiceError::WhisperModelNotLoaded);
         }
 
-        {
+        self.start_capture(listen.device.as_deref())?;
+
+        // Monitor the recording (with voice activity detection when auto-stop is enabled)
+        let onset = self.wait_for_stop(listen, None).await?;
+        self.check_cancelled()?;
+        
+        // Stop recording and get transcription
+        let mut recording = {
+            let audio_capture = self.audio_capture.lock().unwrap();
+            audio_capture.stop_capture()?
+        };
+        self.save_raw_debug_audio(&recording);
+
+        // With auto-stop, a recording in which nobody spoke is not worth a Whisper run
+        if listen.auto_stop && onset.is_none() {
+            debug_eprintln!(self.debug_config.enabled, "🔇 No speech detected, skipping transcription");
+            return Ok(no_speech_transcription(recording.duration_seconds()));
+        }
+
+        // Whisper only sees the audio from shortly before the speaker started
+        if let Some(onset) = onset {
+            let leading = onset.saturating_sub(samples_for_duration_ms(recording.sample_rate(), listen.pre_roll_ms));
+            debug_eprintln!(self.debug_config.enabled, "✂️ Trimming {:.2}s of silence before speech",
+                    leading as f32 / recording.sample_rate().max(1) as f32);
+            recording.trim_start(leading);
+        }
+
+        self.transcribe_recording(recording, options).await
+    }
+
+    /// Like `start_listening_with_options`, but transcribes the recording while it is in progress.
+    ///
+    /// Partial and final text is sent on `events` as it becomes available; the returned
+    /// transcription is made of the final segments.
+    pub async fn start_streaming_with_options(
+        &self,
+        listen: &ListenOptions,
+        options: &TranscriptionOptions,
+        streaming: &StreamingOptions,
+        events: UnboundedSender<StreamEvent>,
+    ) -> Result<Transcription> {
+        if !self.engine.has_model() {
+            return Err(VoiceError::WhisperModelNotLoaded);
+        }
+
+        self.start_capture(listen.device.as_deref())?;
+
+        // Partial transcriptions run on their own task so silence detection never waits for Whisper.
+        // At most one update request is queued; the rest are dropped while the task is busy.
+        let stream = StreamState::new(streaming.clone(), events);
+        let (updates, update_requests) = tokio::sync::mpsc::channel(1);
+        let worker = tokio::spawn(self.clone().stream_partials(stream, update_requests, options.clone()));
+        let onset = self.wait_for_stop(listen, Some(&updates)).await?;
+
+        // Stop recording, then let the partial transcription in progress land
+        drop(updates);
+        let stopped = self.check_cancelled().and_then(|_| self.audio_capture.lock().unwrap().stop_capture());
+        let mut stream = worker.await
+            .map_err(|e| VoiceError::WhisperTranscription(format!("Streaming task failed: {}", e)))?;
+        let recording = stopped?;
+        self.save_raw_debug_audio(&recording);
+
+        // As without streaming, silence is not transcribed
+        if listen.auto_stop && onset.is_none() {
+            debug_eprintln!(self.debug_config.enabled, "🔇 No speech detected, skipping transcription");
+            return Ok(stream.finish(recording.duration_seconds()));
+        }
+        if let Some(onset) = onset {
+            stream.skip_to(onset.saturating_sub(samples_for_duration_ms(recording.sample_rate(), listen.pre_roll_ms)));
+        }
+
+        // Commit whatever is left after the last update
+        let remaining = recording.read_from(stream.committed_samples())?;
+        self.transcribe_stream_window(&mut stream, remaining, options, true).await;
+
+        Ok(stream.finish(recording.duration_seconds()))
+    }
+
+    /// Keep the microphone open and transcribe every utterance until `stop_continuous` is called.
+    ///
+    /// Speech is split into utterances at pauses longer than `listen.silence_timeout_ms`, and
+    /// an utterance running longer than `listen.timeout_ms` is cut there. Utterances are
+    /// transcribed in the background, in order, while listening goes on, and each result is sent
+    /// on `events`. An utterance that fails to transcribe, or that arrives while
+    /// `MAX_PENDING_UTTERANCES` are already waiting, is reported as an error event instead.
+    /// Audio that has been transcribed is freed, so a session can run for hours.
+    /// Returns the number of utterances sent.
+    pub async fn listen_continuous(
+        &self,
+        listen: &ListenOptions,
+        options: &TranscriptionOptions,
+        events: UnboundedSender<ContinuousEvent>,
+    ) -> Result<usize> {
+        if !self.engine.has_model() {
+            return Err(VoiceError::WhisperModelNotLoaded);
+        }
+
+        self.start_capture(listen.device.as_deref())?;
+        self.continuous.store(true, Ordering::Relaxed);
+
+        let sample_rate = self.audio_capture.lock().unwrap().sample_rate();
+        let vad = listen.vad.clone().unwrap_or_else(|| self.get_vad_options());
+        let mut segmenter = UtteranceSegmenter::new(listen, vad, sample_rate);
+
+        // Transcribe on a separate task so listening never waits for Whisper
+        let (pending, mut pending_receiver) = tokio::sync::mpsc::channel::<(std::ops::Range<usize>, AudioBuffer)>(MAX_PENDING_UTTERANCES);
+        let worker = {
+            let service = self.clone();
+            let options = options.clone();
+            let events = events.clone();
+            tokio::spawn(async move {
+                let mut sent = 0;
+                while let Some((range, audio)) = pending_receiver.recv().await {
+                    match service.transcribe_audio_with_options(audio, &options).await {
+                        Ok(transcription) if !transcription.text.trim().is_empty() => {
+                            let _ = events.send(ContinuousEvent::Utterance(Utterance::new(sent, range, sample_rate, transcription)));
+                            sent += 1;
+                        }
+                        Ok(_) | Err(VoiceError::AudioTooShort { .. }) | Err(VoiceError::AudioTooQuiet { .. }) => {}
+                        Err(VoiceError::Cancelled) => {}
+                        Err(e) => {
+                            debug_eprintln!(service.debug_config.enabled, "Warning: Failed to transcribe utterance: {}", e);
+                            let _ = events.send(ContinuousEvent::error(&range, sample_rate, e));
+                        }
+                    }
+                }
+                sent
+            })
+        };
+
+        let check_interval = Duration::from_millis(CHECK_INTERVAL_MS);
+        let mut read_error = None;
+        while self.continuous.load(Ordering::Relaxed) && self.is_recording() && !self.cancel.is_cancelled() {
+            self.pause(check_interval).await;
+
             let audio_capture = self.audio_capture.lock().unwrap();
-            audio_capture.start_capture()?;
+            let audio = match audio_capture.get_audio_since(segmenter.position()) {
+                Ok(audio) => audio,
+                Err(e) => {
+                    read_error = Some(e);
+                    break;
+                }
+            };
+            for range in segmenter.push(&audio.samples) {
+                debug_eprintln!(self.debug_config.enabled, "🗣️ Utterance from {:.1}s to {:.1}s",
+                        range.start as f32 / sample_rate as f32, range.end as f32 / sample_rate as f32);
+                let queued = audio_capture
+                    .get_audio_range(range.start, range.len())
+                    .and_then(|audio| match pending.try_send((range.clone(), audio)) {
+                        Ok(()) => Ok(()),
+                        // Waiting for the worker would stall listening, so the utterance is given up
+                        Err(_) => Err(VoiceError::WhisperTranscription(format!(
+                            "Transcription fell behind with {} utterances waiting; utterance dropped", MAX_PENDING_UTTERANCES
+                        ))),
+                    });
+                if let Err(e) = queued {
+                    debug_eprintln!(self.debug_config.enabled, "Warning: {}", e);
+                    let _ = events.send(ContinuousEvent::error(&range, sample_rate, e));
+                }
+            }
+            audio_capture.discard_audio_before(segmenter.retain_from());
         }
+        self.continuous.store(false, Ordering::Relaxed);
+        let stopped = match read_error {
+            // Audio that cannot be read back is lost, so the session ends with the error
+            Some(e) => {
+                let _ = self.cancel_listening();
+                Err(e)
+            }
+            None => self.check_cancelled(),
+        };
+        if let Err(e) = stopped {
+            // Utterances still queued are dropped by the cancelled transcriptions, or sent before the read error
+            drop(pending);
+            let _ = worker.await;
+            return Err(e);
+        }
+
+        // Finish the utterance in progress, unless another call took the recording away
+        let recording = self.audio_capture.lock().unwrap().stop_capture();
+        if let Ok(recording) = recording {
+            let rest = recording.read_from(segmenter.position())?;
+            let mut ranges = segmenter.push(&rest.samples);
+            ranges.extend(segmenter.finish());
+            for range in ranges {
+                let _ = pending.send((range.clone(), recording.read(range.start, range.len())?)).await;
+            }
+        }
+
+        drop(pending);
+        worker
+            .await
+            .map_err(|e| VoiceError::WhisperTranscription(format!("Transcription task failed: {}", e)))
+    }
 
-        if auto_stop {
-            // Start the auto-stop monitoring with voice activity detection
-            return self.listen_with_auto_stop(timeout_ms, silence_timeout_ms).await;
+    /// End `listen_continuous` after transcribing the utterance in progress
+    pub fn stop_continuous(&self) -> Result<()> {
+        if self.continuous.swap(false, Ordering::Relaxed) {
+            Ok(())
         } else {
-            Ok("Started listening...".to_string())
+            Err(VoiceError::NotRecording)
         }
     }
 
-    pub async fn stop_listening(&self) -> Result<String> {
-        let audio_data = {
+    /// Whether `listen_continuous` is running
+    pub fn is_continuous(&self) -> bool {
+        self.continuous.load(Ordering::Relaxed)
+    }
+
+    pub async fn stop_listening(&self) -> Result<Transcription> {
+        self.stop_listening_with_options(&self.transcription_options).await
+    }
+
+    pub async fn stop_listening_with_options(&self, options: &TranscriptionOptions) -> Result<Transcription> {
+        let recording = {
             let audio_capture = self.audio_capture.lock().unwrap();
             audio_capture.stop_capture()?
         };
+        self.save_raw_debug_audio(&recording);
 
-        // Save raw audio for debugging if enabled
-        if self.debug_config.enabled && self.debug_config.save_raw {
-            if let Err(e) = self.audio_file_handler.save_debug_audio(&audio_data, "raw", DEFAULT_SAMPLE_RATE) {
-                debug_eprintln!(self.debug_config.enabled, "Warning: Failed to save raw audio debug file: {}", e);
-            }
+        self.transcribe_recording(recording, options).await
+    }
+
+    /// Save raw audio for debugging if enabled (recordings spilled to disk are too long to duplicate)
+    fn save_raw_debug_audio(&self, recording: &RecordedAudio) {
+        if !(self.debug_config.enabled && self.debug_config.save_raw) {
+            return;
+        }
+        if recording.is_spilled() {
+            debug_eprintln!(self.debug_config.enabled, "🔧 Debug: Not saving raw audio for a {:.0}s recording that exceeded the memory limit", recording.duration_seconds());
+            return;
         }
+        let result = recording.to_buffer().and_then(|audio| self.audio_file_handler.save_debug_audio(&audio, "raw"));
+        if let Err(e) = result {
+            debug_eprintln!(self.debug_config.enabled, "Warning: Failed to save raw audio debug file: {}", e);
+        }
+    }
 
-        let transcription = self.transcribe_audio(audio_data).await?;
-        Ok(transcription)
+    /// Transcribe a finished recording; one longer than a chunk is transcribed chunk by chunk
+    pub async fn transcribe_recording(&self, recording: RecordedAudio, options: &TranscriptionOptions) -> Result<Transcription> {
+        self.transcribe_recording_with_progress(recording, options, |_| {}).await
+    }
+
+    /// Like `transcribe_recording`, calling `progress` after every chunk of a long recording
+    pub async fn transcribe_recording_with_progress(
+        &self,
+        recording: RecordedAudio,
+        options: &TranscriptionOptions,
+        mut progress: impl FnMut(&ChunkProgress) + Send + 'static,
+    ) -> Result<Transcription> {
+        let chunk_samples = samples_for_duration_ms(recording.sample_rate(), self.chunking_options.chunk_ms);
+        if recording.len() <= chunk_samples {
+            return self.transcribe_audio_with_options(recording.to_buffer()?, options).await;
+        }
+        if !self.engine.has_model() {
+            return Ok(placeholder_transcription(recording.len()));
+        }
+
+        debug_eprintln!(self.debug_config.enabled, "📼 Transcribing {:.0}s of audio in chunks of up to {}s",
+                recording.duration_seconds(), self.chunking_options.chunk_ms / 1000);
+
+        let engine = Arc::clone(&self.engine);
+        let slots = self.transcription_slots.clone();
+        let priority = self.priority;
+        let cancel = self.cancel.clone();
+        let mut transcribing = self.transcription_progress();
+        let options = options.clone();
+        let chunking = self.chunking_options.clone();
+        let debug_enabled = self.debug_config.enabled;
+        tokio::task::spawn_blocking(move || {
+            let total_seconds = recording.duration_seconds();
+            let processed_seconds = Cell::new(0.0);
+            let mut report = |chunk: &ChunkProgress| {
+                debug_eprintln!(debug_enabled, "📼 Chunk {}: {:.0}s of {:.0}s transcribed", 
+                        chunk.chunk, chunk.processed_seconds, chunk.total_seconds);
+                processed_seconds.set(chunk.processed_seconds);
+                progress(chunk);
+            };
+            // A worker is taken per chunk, so higher priority calls can run between the chunks of a long file
+            chunking::transcribe_chunked(&recording, &chunking, &mut report, |audio| {
+                if cancel.is_cancelled() {
+                    return Err(VoiceError::Cancelled);
+                }
+                let _slot = slots.acquire_blocking(priority)?;
+                let chunk_seconds = audio.duration_seconds();
+                engine.transcribe_with_progress(audio, &options, &cancel, &mut |percent| {
+                    let seconds = processed_seconds.get() + chunk_seconds * percent / 100.0;
+                    transcribing((seconds / total_seconds * 100.0).min(100.0));
+                })
+            })
+        })
+        .await
+        .map_err(|e| VoiceError::WhisperTranscription(format!("Transcription task failed: {}", e)))?
+    }
+
+    /// Stop recording without transcribing, discarding the captured audio
+    pub fn cancel_listening(&self) -> Result<()> {
+        self.audio_capture.lock().unwrap().cancel_capture()
     }
 
     pub fn is_recording(&self) -> bool {
         self.audio_capture.lock().unwrap().is_recording()
     }
 
+    pub fn recording_status(&self) -> RecordingStatus {
+        let audio_capture = self.audio_capture.lock().unwrap();
+        let sample_rate = audio_capture.sample_rate();
+        let sample_count = audio_capture.get_audio_sample_count();
+        RecordingStatus {
+            recording: audio_capture.is_recording(),
+            duration_seconds: if sample_rate > 0 { sample_count as f32 / sample_rate as f32 } else { 0.0 },
+            sample_rate,
+        }
+    }
+
+    pub fn has_model(&self) -> bool {
+        self.engine.has_model()
+    }
+
     pub fn get_audio_sample_count(&self) -> usize {
         self.audio_capture.lock().unwrap().get_audio_sample_count()
     }
 
 
-    pub async fn transcribe_audio(&self, audio_data: Vec<f32>) -> Result<String> {
-        let whisper_transcriber = self.whisper_transcriber.lock().await;
-        
-        let transcription = whisper_transcriber.transcribe_with_validation(audio_data.clone()).await?;
+    pub async fn transcribe_audio(&self, audio: AudioBuffer) -> Result<Transcription> {
+        self.transcribe_audio_with_options(audio, &self.transcription_options).await
+    }
+
+    pub async fn transcribe_audio_with_options(&self, audio: AudioBuffer, options: &TranscriptionOptions) -> Result<Transcription> {
+        if audio.is_empty() {
+            return Ok(Transcription::from_text("No audio data recorded"));
+        }
+
+        let transcription = if self.engine.has_model() {
+            self.run_engine(audio.clone(), options).await?
+        } else {
+            // Fallback to placeholder if no model loaded
+            placeholder_transcription(audio.len())
+        };
         
         // Save processed audio for debugging if enabled
         if self.debug_config.enabled && self.debug_config.save_processed {
-            let audio_processor = whisper_transcriber.get_audio_processor();
-            if let Ok(processed_audio) = audio_processor.prepare_for_whisper(&audio_data) {
-                if let Err(e) = self.audio_file_handler.save_debug_audio(&processed_audio, "processed", WHISPER_SAMPLE_RATE) {
+            let processor = AudioProcessor::new(self.debug_config.enabled).with_resample_quality(options.resample_quality);
+            if let Ok(processed_audio) = processor.prepare_for_whisper(&audio) {
+                if let Err(e) = self.audio_file_handler.save_debug_audio(&processed_audio, "processed") {
                     debug_eprintln!(self.debug_config.enabled, "Warning: Failed to save processed audio debug file: {}", e);
                 }
             }
@@ -146,15 +526,291 @@ impl VoiceToTextService {
         self.debug_config.enabled = enabled;
     }
 
-    pub async fn transcribe_wav_file(&self, wav_path: &str) -> Result<String> {
-        let audio_data = self.audio_file_handler.load_wav_file(wav_path)?;
-        self.transcribe_audio(audio_data).await
+    /// Options used when a call does not supply its own
+    pub fn get_transcription_options(&self) -> &TranscriptionOptions {
+        &self.transcription_options
+    }
+
+    pub fn set_transcription_options(&mut self, options: TranscriptionOptions) {
+        self.transcription_options = options;
+    }
+
+    /// How many transcriptions run at once; each one keeps a Whisper state of its own
+    pub fn get_transcription_workers(&self) -> usize {
+        self.transcription_workers
+    }
+
+    /// Let up to `workers` transcriptions run at once, e.g. for several MCP requests.
+    ///
+    /// The model is loaded once and shared, but every concurrent transcription needs a Whisper
+    /// state, so memory use grows with `workers`; lowering it frees the extra states. Clones made
+    /// earlier keep the previous limit on concurrent transcriptions.
+    pub fn set_transcription_workers(&mut self, workers: usize) -> Result<()> {
+        if workers == 0 {
+            return Err(VoiceError::InvalidOptions("At least one transcription worker is needed".to_string()));
+        }
+        self.engine.set_workers(workers);
+        self.transcription_slots = PrioritySemaphore::new(workers);
+        self.transcription_workers = workers;
+        Ok(())
+    }
+
+    /// Limits applied by job queues created from this service
+    pub fn get_job_limits(&self) -> &JobLimits {
+        &self.job_limits
+    }
+
+    pub fn set_job_limits(&mut self, limits: JobLimits) {
+        self.job_limits = limits;
+    }
+
+    /// A clone whose transcriptions wait behind higher `priority` work and stop once `cancel` is cancelled
+    pub(crate) fn for_job(&self, priority: Priority, cancel: CancellationToken) -> Self {
+        Self {
+            priority,
+            cancel,
+            ..self.clone()
+        }
+    }
+
+    /// A clone whose work stops once `cancel` is cancelled, e.g. when an MCP client gives up on a request.
+    ///
+    /// A recording in progress is stopped and its audio discarded, a transcription waiting for a
+    /// worker leaves the queue and one already running is aborted inside Whisper. All of them
+    /// return `VoiceError::Cancelled`. The clone shares the microphone and workers with this service.
+    pub fn with_cancellation(&self, cancel: CancellationToken) -> Self {
+        Self {
+            cancel,
+            ..self.clone()
+        }
+    }
+
+    /// A clone that sends `events` while it records and transcribes: the elapsed time, input
+    /// level and voice activity of a recording, and how much of the audio has been transcribed
+    pub fn with_progress(&self, events: UnboundedSender<ProgressEvent>) -> Self {
+        Self {
+            progress: Some(events),
+            ..self.clone()
+        }
+    }
+
+    fn report_progress(&self, event: ProgressEvent) {
+        if let Some(ref events) = self.progress {
+            let _ = events.send(event);
+        }
+    }
+
+    /// Reports transcription progress, holding the percentage when a failed decode is retried
+    fn transcription_progress(&self) -> impl FnMut(f32) + Send + 'static {
+        let events = self.progress.clone();
+        let mut reported = f32::NEG_INFINITY;
+        move |percent| {
+            if let Some(ref events) = events {
+                if percent > reported {
+                    reported = percent;
+                    let _ = events.send(ProgressEvent::Transcribing { percent });
+                }
+            }
+        }
+    }
+
+    /// The token that stops this service's work when cancelled
+    pub fn cancellation_token(&self) -> &CancellationToken {
+        &self.cancel
+    }
+
+    /// Stop and discard the recording when the service has been cancelled
+    fn check_cancelled(&self) -> Result<()> {
+        if !self.cancel.is_cancelled() {
+            return Ok(());
+        }
+        match self.cancel_listening() {
+            Ok(()) | Err(VoiceError::NotRecording) => Err(VoiceError::Cancelled),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Sleep for `duration`, or less if the service is cancelled in the meantime
+    async fn pause(&self, duration: Duration) {
+        tokio::select! {
+            _ = sleep(duration) => {}
+            _ = self.cancel.cancelled() => {}
+        }
+    }
+
+    /// How audio longer than one chunk is split up for transcription
+    pub fn get_chunking_options(&self) -> &ChunkingOptions {
+        &self.chunking_options
+    }
+
+    pub fn set_chunking_options(&mut self, options: ChunkingOptions) {
+        self.chunking_options = options;
+    }
+
+    /// Voice activity detection used to auto-stop recordings that do not choose their own
+    pub fn get_vad_options(&self) -> VadOptions {
+        self.vad_options.lock().unwrap().clone()
+    }
+
+    pub fn set_vad_options(&self, options: VadOptions) {
+        *self.vad_options.lock().unwrap() = options;
+    }
+
+    /// Save calibrations to `path`; `None` keeps them for this session only
+    pub fn set_profile_path(&mut self, path: Option<PathBuf>) {
+        self.profile_path = path;
+    }
+
+    /// Record `duration_ms` of room tone, measure it and switch to the recommended voice
+    /// activity detection settings, saving them to the profile path when `save` is set
+    pub async fn calibrate(&self, duration_ms: u64, device: Option<&str>, save: bool) -> Result<CalibrationReport> {
+        self.start_capture(device)?;
+        self.pause(Duration::from_millis(duration_ms)).await;
+        self.check_cancelled()?;
+        let recording = {
+            let audio_capture = self.audio_capture.lock().unwrap();
+            audio_capture.stop_capture()?
+        };
+
+        let mut report = calibration::analyze_room_tone(&recording.to_buffer()?, &self.get_vad_options())?;
+        debug_eprintln!(self.debug_config.enabled, "🎚️ Room tone: {:.1} dBFS noise floor, {:.1} dB variation, peak {:.1} dBFS",
+                report.noise_floor_db, report.noise_variation_db, report.peak_db);
+        self.set_vad_options(report.recommended.clone());
+
+        if let (true, Some(path)) = (save, &self.profile_path) {
+            let device = device.or(self.input_device.as_deref());
+            CalibrationProfile::from_report(&report, device).save(path)?;
+            report.profile_path = Some(path.clone());
+        }
+        Ok(report)
+    }
+
+    /// Input device used when a recording does not name one; `None` is the system default
+    pub fn get_input_device(&self) -> Option<&str> {
+        self.input_device.as_deref()
+    }
+
+    pub fn set_input_device(&mut self, device: Option<String>) {
+        self.input_device = device;
+    }
+
+    pub fn list_input_devices(&self) -> Result<Vec<InputDeviceInfo>> {
+        AudioCapture::list_input_devices()
+    }
+
+    /// Keep at most `bytes` of a recording in memory and spill the rest to a temporary file
+    pub fn set_memory_limit(&self, bytes: usize) {
+        self.audio_capture.lock().unwrap().set_memory_limit(bytes);
+    }
+
+    /// Record from `source` instead of the system's input devices, e.g. a `ReplaySource` or `SignalSource`
+    pub fn set_audio_source(&self, source: impl AudioSource + 'static) -> Result<()> {
+        self.audio_capture.lock().unwrap().set_source(Box::new(source))
+    }
+
+    /// Transcribe an audio file in any supported format (WAV, MP3, FLAC, Ogg Vorbis, AAC/M4A)
+    pub async fn transcribe_audio_file(&self, path: &str) -> Result<Transcription> {
+        self.transcribe_audio_file_with_options(path, &self.transcription_options).await
+    }
+
+    pub async fn transcribe_audio_file_with_options(&self, path: &str, options: &TranscriptionOptions) -> Result<Transcription> {
+        self.transcribe_audio_file_with_progress(path, options, |_| {}).await
+    }
+
+    /// Transcribe an audio file of any length; long files are decoded into a recording that
+    /// spills to disk beyond the memory limit and transcribed chunk by chunk
+    pub async fn transcribe_audio_file_with_progress(
+        &self,
+        path: &str,
+        options: &TranscriptionOptions,
+        progress: impl FnMut(&ChunkProgress) + Send + 'static,
+    ) -> Result<Transcription> {
+        let memory_limit = self.audio_capture.lock().unwrap().memory_limit();
+        let recording = self.audio_file_handler.load_audio_file_into_recording(path, memory_limit)?;
+        self.transcribe_recording_with_progress(recording, options, progress).await
+    }
+
+    /// Kept for existing callers; accepts every format `transcribe_audio_file` does
+    pub async fn transcribe_wav_file(&self, wav_path: &str) -> Result<Transcription> {
+        self.transcribe_audio_file(wav_path).await
+    }
+
+    /// Wait until fewer than `transcription_workers` transcriptions are running, letting
+    /// higher priority callers go first
+    async fn transcription_slot(&self) -> Result<SlotPermit> {
+        tokio::select! {
+            biased;
+            _ = self.cancel.cancelled() => Err(VoiceError::Cancelled),
+            permit = self.transcription_slots.acquire(self.priority) => permit,
+        }
+    }
+
+    /// Run the engine on a blocking thread so a long transcription does not stall the async runtime
+    async fn run_engine(&self, audio: AudioBuffer, options: &TranscriptionOptions) -> Result<Transcription> {
+        let _permit = self.transcription_slot().await?;
+        let engine = Arc::clone(&self.engine);
+        let options = options.clone();
+        let cancel = self.cancel.clone();
+        let mut progress = self.transcription_progress();
+        tokio::task::spawn_blocking(move || engine.transcribe_with_progress(&audio, &options, &cancel, &mut progress))
+            .await
+            .map_err(|e| VoiceError::WhisperTranscription(format!("Transcription task failed: {}", e)))?
+    }
+
+    /// Transcribe the not yet committed audio and pass the result to the stream
+    async fn transcribe_stream_window(&self, stream: &mut StreamState, audio: AudioBuffer, options: &TranscriptionOptions, flush: bool) {
+        let window_samples = audio.len();
+        let sample_rate = audio.sample_rate;
+        if window_samples == 0 {
+            return;
+        }
+
+        match self.run_engine(audio, options).await {
+            Ok(transcription) => stream.apply(&transcription, window_samples, sample_rate, flush),
+            // Silence or a very short window: nothing to report, but it still counts towards the window length
+            Err(VoiceError::AudioTooShort { .. }) | Err(VoiceError::AudioTooQuiet { .. }) => {
+                stream.apply(&Transcription::default(), window_samples, sample_rate, flush)
+            }
+            Err(e) => {
+                debug_eprintln!(self.debug_config.enabled, "Warning: Streaming transcription failed: {}", e);
+                stream.apply(&Transcription::default(), window_samples, sample_rate, flush)
+            }
+        }
+    }
+
+    /// Transcribe the uncommitted audio on each due update request, until the requests stop.
+    ///
+    /// Each request carries the first sample worth transcribing, which skips the silence before the speaker.
+    async fn stream_partials(self, mut stream: StreamState, mut requests: tokio::sync::mpsc::Receiver<usize>, options: TranscriptionOptions) -> StreamState {
+        while let Some(start) = requests.recv().await {
+            stream.skip_to(start);
+            if !stream.is_due() {
+                continue;
+            }
+            let audio = self.audio_capture.lock().unwrap().get_audio_since(stream.committed_samples());
+            match audio {
+                Ok(audio) => self.transcribe_stream_window(&mut stream, audio, &options, false).await,
+                Err(e) => debug_eprintln!(self.debug_config.enabled, "Warning: Failed to read captured audio: {}", e),
+            }
+        }
+        stream
     }
 
-    async fn listen_with_auto_stop(&self, timeout_ms: u64, silence_timeout_ms: u64) -> Result<String> {
+    /// Wait until the timeout, a stop request or (with `auto_stop`) trailing silence, asking for stream updates if there is a stream.
+    ///
+    /// With `auto_stop` the recording also ends when nobody starts speaking within the onset
+    /// timeout. Returns the sample index where speech began, if it was detected. If the captured
+    /// audio cannot be read back, the recording is cancelled and the error returned.
+    async fn wait_for_stop(&self, listen: &ListenOptions, stream_updates: Option<&tokio::sync::mpsc::Sender<usize>>) -> Result<Option<usize>> {
+        let ListenOptions { timeout_ms, silence_timeout_ms, onset_timeout_ms, pre_roll_ms, auto_stop, .. } = *listen;
         let start_time = Instant::now();
         let mut last_activity_time = Instant::now();
+        let mut vad_position = 0usize;
+        let sample_rate = self.audio_capture.lock().unwrap().sample_rate();
+        let mut vad = VoiceActivityDetector::new(listen.vad.clone().unwrap_or_else(|| self.get_vad_options()), sample_rate);
         let check_interval = Duration::from_millis(CHECK_INTERVAL_MS);
+        let progress_interval = Duration::from_millis(RECORDING_PROGRESS_INTERVAL_MS);
+        let mut last_progress_time = start_time;
         
         loop {
             // Check for overall timeout
@@ -162,55 +818,75 @@ impl VoiceToTextService {
                 break;
             }
             
-            // Check if recording was stopped by voice command
-            if !self.is_recording() {
+            // Check if recording was stopped by voice command or the caller gave up
+            if !self.is_recording() || self.cancel.is_cancelled() {
                 break;
             }
             
-            // Check for voice activity
+            // Run voice activity detection on the audio that arrived since the last check
             let has_activity = {
-                let audio_data = {
-                    let audio_capture = self.audio_capture.lock().unwrap();
-                    audio_capture.get_current_audio_data()
+                let audio = self.audio_capture.lock().unwrap().get_audio_since(vad_position);
+                let audio = match audio {
+                    Ok(audio) => audio,
+                    Err(e) => {
+                        // Audio that cannot be read back is lost, so keeping the recording going is pointless
+                        let _ = self.cancel_listening();
+                        return Err(e);
+                    }
                 };
-                
-                if audio_data.len() > samples_for_duration_ms(DEFAULT_SAMPLE_RATE, RECENT_SAMPLES_DURATION_MS) {
-                    let whisper_transcriber = self.whisper_transcriber.lock().await;
-                    let audio_processor = whisper_transcriber.get_audio_processor();
-                    audio_processor.has_voice_activity(&audio_data)
-                } else {
-                    false
+                vad_position += audio.len();
+                let was_speaking = vad.is_speech();
+                let speaking = vad.process(&audio.samples);
+                if speaking != was_speaking {
+                    debug_eprintln!(self.debug_config.enabled, "🗣️ Speech {} at {:.1} dBFS (noise floor {:.1} dBFS)",
+                            if speaking { "started" } else { "ended" }, vad.level_db(), vad.noise_floor_db());
                 }
+                speaking
             };
+
+            if let Some(updates) = stream_updates {
+                // With auto-stop nothing is worth transcribing until someone speaks, and then only
+                // from the pre-roll on. A full queue means an update is already waiting.
+                let start = if auto_stop {
+                    vad.speech_onset().map(|onset| onset.saturating_sub(samples_for_duration_ms(sample_rate, pre_roll_ms)))
+                } else {
+                    Some(0)
+                };
+                if let Some(start) = start {
+                    let _ = updates.try_send(start);
+                }
+            }
+
+            if self.progress.is_some() && last_progress_time.elapsed() >= progress_interval {
+                last_progress_time = Instant::now();
+                self.report_progress(ProgressEvent::Recording {
+                    elapsed_seconds: start_time.elapsed().as_secs_f32(),
+                    level_db: vad.level_db(),
+                    voice: vad.activity(),
+                });
+            }
+            
+            if !auto_stop {
+                self.pause(check_interval).await;
+                continue;
+            }
             
             if has_activity {
                 last_activity_time = Instant::now();
-            } else {
-                // Check if we've been silent for too long
+            } else if vad.speech_detected() {
+                // Check if we've been silent for too long since the speaker stopped
                 if last_activity_time.elapsed().as_millis() > silence_timeout_ms as u128 {
-                    // Only auto-stop if we have some recorded audio
-                    let has_audio = {
-                        let audio_capture = self.audio_capture.lock().unwrap();
-                        audio_capture.get_audio_sample_count() > samples_for_duration_ms(DEFAULT_SAMPLE_RATE, 500) // 0.5 seconds
-                    };
-                    
-                    if has_audio {
-                        break;
-                    } else {
-                        // Also auto-stop even without audio if we've been silent long enough
-                        // This prevents hanging when no speech is detected
-                        if last_activity_time.elapsed().as_millis() > (silence_timeout_ms * 2) as u128 {
-                            break;
-                        }
-                    }
+                    break;
                 }
+            } else if start_time.elapsed().as_millis() > onset_timeout_ms as u128 {
+                debug_eprintln!(self.debug_config.enabled, "🔇 No speech within {}ms, stopping", onset_timeout_ms);
+                break;
             }
             
-            sleep(check_interval).await;
+            self.pause(check_interval).await;
         }
-        
-        // Stop recording and get transcription
-        self.stop_listening().await
+
+        Ok(if auto_stop { vad.speech_onset() } else { None })
     }
 
 }
@@ -222,10 +898,25 @@ impl std::fmt::Debug for VoiceToTextService {
             .field("audio_data_len", &self.get_audio_sample_count())
             .field("has_whisper_context", &"<async_mutex>")
             .field("debug_config", &self.debug_config)
+            .field("transcription_options", &self.transcription_options)
             .finish()
     }
 }
 
+/// Result for a recording in which nobody spoke
+fn no_speech_transcription(duration: f32) -> Transcription {
+    Transcription {
+        text: "No speech detected in audio".to_string(),
+        duration,
+        ..Default::default()
+    }
+}
+
+/// Stand-in result when no model is loaded
+fn placeholder_transcription(sample_count: usize) -> Transcription {
+    Transcription::from_text(format!("Transcribed {} audio samples (Whisper model not loaded - use new_with_model() to load a model)", sample_count))
+}
+
 impl Default for VoiceToTextService {
     fn default() -> Self {
         Self::new()
@@ -272,19 +963,22 @@ mod tests {
     #[tokio::test]
     async fn test_transcribe_empty_audio() {
         let service = VoiceToTextService::new();
-        let result = service.transcribe_audio(vec![]).await.unwrap();
+        let result = service.transcribe_audio(AudioBuffer::new(vec![], DEFAULT_SAMPLE_RATE)).await.unwrap().text;
         assert_eq!(result, "No audio data recorded");
     }
 
     #[tokio::test]
     async fn test_transcribe_with_audio_data() {
-        let service = VoiceToTextService::new();
+        let engine = MockEngine::new().then_text("Hello world.");
+        let service = VoiceToTextService::new_with_engine(engine.clone(), DebugConfig::default());
         let audio_data = vec![0.1, 0.2, 0.3, 0.4, 0.5];
-        let result = service.transcribe_audio(audio_data.clone()).await.unwrap();
-        assert!(result.contains(&audio_data.len().to_string()) || 
-                result.contains("model not loaded"));
-        assert!(result.contains("Transcribed") || 
-                result.contains("model not loaded"));
+        let result = service.transcribe_audio(AudioBuffer::new(audio_data.clone(), DEFAULT_SAMPLE_RATE)).await.unwrap().text;
+        assert_eq!(result, "Hello world.");
+
+        let calls = engine.calls();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].sample_count, audio_data.len());
+        assert_eq!(calls[0].sample_rate, DEFAULT_SAMPLE_RATE);
     }
 
     #[test]
@@ -331,17 +1025,17 @@ mod tests {
         
         // Test with single sample
         let single_sample = vec![0.5];
-        let result = service.transcribe_audio(single_sample).await.unwrap();
+        let result = service.transcribe_audio(AudioBuffer::new(single_sample, DEFAULT_SAMPLE_RATE)).await.unwrap().text;
         assert!(result.contains("1") || result.contains("model not loaded"));
         
         // Test with many samples
         let many_samples = vec![0.1; 10000];
-        let result = service.transcribe_audio(many_samples).await.unwrap();
+        let result = service.transcribe_audio(AudioBuffer::new(many_samples, DEFAULT_SAMPLE_RATE)).await.unwrap().text;
         assert!(result.contains("10000") || result.contains("model not loaded"));
         
         // Test with extreme values
         let extreme_samples = vec![f32::MAX, f32::MIN, 0.0, f32::INFINITY, f32::NEG_INFINITY];
-        let result = service.transcribe_audio(extreme_samples).await;
+        let result = service.transcribe_audio(AudioBuffer::new(extreme_samples, DEFAULT_SAMPLE_RATE)).await;
         assert!(result.is_ok()); // Should handle extreme values gracefully
     }
 
@@ -350,7 +1044,7 @@ mod tests {
         // Test that new() creates service without model
         let service = VoiceToTextService::new();
         let audio_data = vec![0.1, 0.2, 0.3];
-        let result = service.transcribe_audio(audio_data).await.unwrap();
+        let result = service.transcribe_audio(AudioBuffer::new(audio_data, DEFAULT_SAMPLE_RATE)).await.unwrap().text;
         assert!(result.contains("model not loaded"));
         
         // Test that new_with_model() with invalid path returns error
@@ -388,82 +1082,31 @@ mod tests {
     }
 
     #[test]
-    fn test_platform_specific_acceleration_features() {
-        // Test that the correct platform-specific dependencies are configured
-        #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
-        {
-            // macOS Apple Silicon should have platform-specific config
-            println!("✅ macOS Apple Silicon: Metal + CoreML + NEON acceleration configured");
-            assert_eq!(std::env::consts::OS, "macos");
-            assert_eq!(std::env::consts::ARCH, "aarch64");
-        }
-        
-        #[cfg(all(target_os = "macos", target_arch = "x86_64"))]
-        {
-            // macOS Intel should have platform-specific config
-            println!("✅ macOS Intel: Metal acceleration configured");
-            assert_eq!(std::env::consts::OS, "macos");
-            assert_eq!(std::env::consts::ARCH, "x86_64");
-        }
-        
-        #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
-        {
-            // Linux x86_64 should have platform-specific config
-            println!("✅ Linux x86_64: CUDA acceleration configured");
-            assert_eq!(std::env::consts::OS, "linux");
-            assert_eq!(std::env::consts::ARCH, "x86_64");
-        }
-        
-        #[cfg(all(target_os = "windows", target_arch = "x86_64"))]
-        {
-            // Windows x86_64 should have platform-specific config
-            println!("✅ Windows x86_64: CUDA acceleration configured");
-            assert_eq!(std::env::consts::OS, "windows");
-            assert_eq!(std::env::consts::ARCH, "x86_64");
-        }
-        
-        #[cfg(not(any(
-            all(target_os = "macos", target_arch = "aarch64"),
-            all(target_os = "macos", target_arch = "x86_64"),
-            all(target_os = "linux", target_arch = "x86_64"),
-            all(target_os = "windows", target_arch = "x86_64")
-        )))]
-        {
-            println!("✅ Generic platform: CPU-only implementation configured");
-        }
+    fn test_acceleration_features_match_build() {
+        // Acceleration is whatever the build enabled, whatever the target platform
+        let features = platform::acceleration_features();
+        println!("✅ {} {}: {}", std::env::consts::OS, std::env::consts::ARCH,
+                 if features.is_empty() { "CPU only".to_string() } else { features.join(" + ") });
+
+        assert_eq!(features.contains(&"CUDA"), cfg!(feature = "cuda"));
+        assert_eq!(features.contains(&"Metal"), cfg!(feature = "metal"));
+        assert_eq!(features.contains(&"CoreML"), cfg!(feature = "coreml"));
+        assert_eq!(features.contains(&"Vulkan"), cfg!(feature = "vulkan"));
+        assert_eq!(features.contains(&"OpenBLAS"), cfg!(feature = "openblas"));
     }
 
-    #[test]
-    fn test_hardware_acceleration_runtime_info() {
-        // Test that we can identify the current platform's acceleration capabilities
-        let platform = std::env::consts::OS;
-        let arch = std::env::consts::ARCH;
-        
-        match (platform, arch) {
-            ("macos", "aarch64") => {
-                println!("🚀 Runtime: macOS Apple Silicon - Metal/CoreML/NEON available");
-                assert_eq!(platform, "macos");
-                assert_eq!(arch, "aarch64");
-            },
-            ("macos", "x86_64") => {
-                println!("🚀 Runtime: macOS Intel - Metal available");
-                assert_eq!(platform, "macos");
-                assert_eq!(arch, "x86_64");
-            },
-            ("linux", "x86_64") => {
-                println!("🚀 Runtime: Linux x86_64 - CUDA available");
-                assert_eq!(platform, "linux");
-                assert_eq!(arch, "x86_64");
-            },
-            ("windows", "x86_64") => {
-                println!("🚀 Runtime: Windows x86_64 - CUDA available");
-                assert_eq!(platform, "windows");
-                assert_eq!(arch, "x86_64");
-            },
-            _ => {
-                println!("🚀 Runtime: Generic platform ({}, {}) - CPU only", platform, arch);
-            }
-        }
+    #[cfg(not(feature = "microphone"))]
+    #[tokio::test]
+    async fn test_recording_without_microphone_support() {
+        let service = VoiceToTextService::new();
+        assert!(matches!(service.start_listening().await, Err(VoiceError::MicrophoneUnsupported)));
+        assert!(matches!(service.list_input_devices(), Err(VoiceError::MicrophoneUnsupported)));
+        assert!(!service.is_recording());
+
+        // Other sources still record
+        service.set_audio_source(SignalSource::new(16000).tone(440.0, 0.5, 200)).unwrap();
+        service.start_listening().await.unwrap();
+        service.cancel_listening().unwrap();
     }
 
     #[tokio::test]
@@ -474,25 +1117,18 @@ mod tests {
         assert!(!service.is_recording());
         assert_eq!(service.get_audio_sample_count(), 0);
         
-        // Test transcription with placeholder model (should handle gracefully)
+        // Engine errors reach the caller unchanged
+        let engine = MockEngine::new().then_error(VoiceError::WhisperTranscription("GPU backend failed".to_string()));
+        let service = VoiceToTextService::new_with_engine(engine, DebugConfig::default());
         let test_audio = vec![0.1, 0.2, 0.3, 0.4, 0.5];
-        let result = service.transcribe_audio(test_audio).await;
-        
-        assert!(result.is_ok());
-        let transcription = result.unwrap();
-        
-        // Should either transcribe or indicate model not loaded
-        assert!(
-            transcription.contains("Transcribed") || 
-            transcription.contains("model not loaded"),
-            "Unexpected transcription result: {}", transcription
-        );
+        let result = service.transcribe_audio(AudioBuffer::new(test_audio, DEFAULT_SAMPLE_RATE)).await;
+        assert!(matches!(result, Err(VoiceError::WhisperTranscription(message)) if message == "GPU backend failed"));
     }
 
     #[tokio::test]
     async fn test_acceleration_fallback_behavior() {
         // Test that the service works even if hardware acceleration fails
-        let service = VoiceToTextService::new();
+        let service = VoiceToTextService::new_with_engine(MockEngine::new(), DebugConfig::default());
         
         // These operations should work regardless of hardware acceleration availability
         let _start_result = service.start_listening().await;
@@ -506,23 +1142,1265 @@ mod tests {
         
         // Should either succeed or return NotRecording error
         match stop_result {
-            Ok(msg) => assert!(msg.contains("Not currently recording") || msg.contains("Transcribed") || msg.contains("No audio data")),
+            Ok(msg) => assert!(msg.text.starts_with("Mock transcription") || msg.text.contains("No audio data")),
             Err(VoiceError::NotRecording) => { /* This is expected */ },
             Err(e) => panic!("Unexpected error: {}", e),
         }
     }
 
+    #[test]
+    fn test_prepare_for_whisper_uses_source_sample_rate() {
+        let audio_processor = AudioProcessor::new(false);
+        
+        // One second of audio should stay one second long whatever rate it was captured at
+        for &rate in &[16000u32, 44100, 48000] {
+            let audio = AudioBuffer::new(vec![0.25; rate as usize], rate);
+            let processed = audio_processor.prepare_for_whisper(&audio).unwrap();
+            
+            assert_eq!(processed.sample_rate, WHISPER_SAMPLE_RATE);
+            assert!((processed.duration_seconds() - 1.0).abs() < 0.01,
+                    "{}Hz input produced {:.3}s of audio", rate, processed.duration_seconds());
+        }
+    }
+
+    fn audio_rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|x| x * x).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    fn sine(frequency: f32, amplitude: f32, sample_rate: u32, count: usize) -> Vec<f32> {
+        (0..count).map(|i| amplitude * (2.0 * std::f32::consts::PI * frequency * i as f32 / sample_rate as f32).sin()).collect()
+    }
+
+    #[test]
+    fn test_resampler_removes_content_above_nyquist() {
+        // A 12kHz tone cannot be represented at 16kHz; linear interpolation folds it down to 4kHz
+        let tone = sine(12000.0, 0.5, 48000, 48000);
+        for quality in [ResampleQuality::Fast, ResampleQuality::Balanced, ResampleQuality::Best] {
+            let resampled = resample::resample(&tone, 48000, 16000, quality).unwrap();
+            assert_eq!(resampled.len(), 16000);
+            let level = audio_rms(&resampled[1000..15000]);
+            assert!(level < 0.005, "{} quality left {:.4} RMS of aliasing", quality, level);
+        }
+
+        // Speech-band content passes at its original level
+        let tone = sine(1000.0, 0.5, 44100, 44100);
+        let resampled = resample::resample(&tone, 44100, 16000, ResampleQuality::Balanced).unwrap();
+        let level = audio_rms(&resampled[1000..15000]);
+        assert!((level - 0.5 / 2f32.sqrt()).abs() < 0.01, "1kHz tone came out at {:.4} RMS", level);
+    }
+
+    #[test]
+    fn test_streaming_resampler_matches_one_shot() {
+        let input = sine(440.0, 0.5, 44100, 20000);
+        for (from, to) in [(44100, 16000), (8000, 16000), (48000, 16000), (16000, 16000)] {
+            let expected = resample::resample(&input, from, to, ResampleQuality::Balanced).unwrap();
+
+            let mut resampler = Resampler::new(from, to, ResampleQuality::Balanced).unwrap();
+            let mut streamed = Vec::new();
+            for chunk in input.chunks(441).flat_map(|c| c.chunks(97)) {
+                resampler.process(chunk, &mut streamed);
+            }
+            resampler.flush(&mut streamed);
+
+            assert_eq!(streamed.len(), expected.len(), "{}Hz -> {}Hz", from, to);
+            assert!(streamed.iter().zip(&expected).all(|(a, b)| (a - b).abs() < 1e-5), "{}Hz -> {}Hz", from, to);
+        }
+
+        assert!(Resampler::new(0, 16000, ResampleQuality::Fast).is_err());
+        assert_eq!("Best".parse::<ResampleQuality>(), Ok(ResampleQuality::Best));
+        assert!("perfect".parse::<ResampleQuality>().is_err());
+    }
+
+    #[test]
+    fn test_load_wav_file_keeps_sample_rate() {
+        let wav_path = std::env::temp_dir().join(format!("voice_to_text_48k_{}.wav", std::process::id()));
+        test_common::write_wav(&wav_path, 48000, 2, [0.5, 0.0].repeat(4800));
+        
+        let handler = AudioFileHandler::new(DebugConfig::default());
+        let audio = handler.load_wav_file(wav_path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&wav_path).ok();
+        
+        assert_eq!(audio.sample_rate, 48000);
+        assert_eq!(audio.len(), 4800);
+        assert!((audio.samples[0] - 0.25).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_audio_format_detection() {
+        assert_eq!(AudioFormat::detect(b"RIFF\x24\x00\x00\x00WAVEfmt "), Some(AudioFormat::Wav));
+        assert_eq!(AudioFormat::detect(b"fLaC\x00\x00\x00\x22"), Some(AudioFormat::Flac));
+        assert_eq!(AudioFormat::detect(b"ID3\x04\x00\x00\x00\x00\x00\x00"), Some(AudioFormat::Mp3));
+        assert_eq!(AudioFormat::detect(&[0xFF, 0xFB, 0x90, 0x64]), Some(AudioFormat::Mp3));
+        assert_eq!(AudioFormat::detect(&[0xFF, 0xF1, 0x50, 0x80]), Some(AudioFormat::Aac));
+        assert_eq!(AudioFormat::detect(b"\x00\x00\x00\x20ftypM4A \x00\x00\x00\x00"), Some(AudioFormat::Mp4));
+        
+        let mut ogg_vorbis = b"OggS\x00\x02".to_vec();
+        ogg_vorbis.resize(28, 0);
+        ogg_vorbis.extend_from_slice(b"\x01vorbis");
+        assert_eq!(AudioFormat::detect(&ogg_vorbis), Some(AudioFormat::OggVorbis));
+        
+        let mut ogg_opus = b"OggS\x00\x02".to_vec();
+        ogg_opus.resize(28, 0);
+        ogg_opus.extend_from_slice(b"OpusHead");
+        assert_eq!(AudioFormat::detect(&ogg_opus), Some(AudioFormat::OggOpus));
+        
+        assert_eq!(AudioFormat::detect(b"plain text, not audio"), None);
+        assert_eq!(AudioFormat::detect(&[]), None);
+    }
+
+    #[test]
+    fn test_decode_compressed_fixtures() {
+        // One-second 48kHz tones written by scripts/make-test-fixtures.py
+        let handler = AudioFileHandler::new(DebugConfig::default());
+        let mut fixtures = vec![("tone.mp3", AudioFormat::Mp3, 42 * 1152), ("tone.m4a", AudioFormat::Mp4, 47 * 1024)];
+        // Opus drops the encoder's pre-skip and end padding, leaving exactly one second
+        if cfg!(feature = "opus") {
+            fixtures.push(("tone.opus", AudioFormat::OggOpus, 48000));
+        }
+        for (name, format, len) in fixtures {
+            let path = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+            assert_eq!(handler.detect_format(&path).unwrap(), format);
+
+            let audio = handler.load_audio_file(&path).unwrap();
+            assert_eq!(audio.sample_rate, 48000, "{}", name);
+            assert_eq!(audio.len(), len, "{}", name);
+
+            // The tone sits near 440Hz: far more energy there than an octave either side
+            let power = |frequency: f32| {
+                let (mut re, mut im) = (0.0f32, 0.0f32);
+                for (i, sample) in audio.samples.iter().enumerate() {
+                    let phase = 2.0 * std::f32::consts::PI * frequency * i as f32 / 48000.0;
+                    re += sample * phase.cos();
+                    im += sample * phase.sin();
+                }
+                re * re + im * im
+            };
+            let rms = (audio.samples.iter().map(|s| s * s).sum::<f32>() / audio.len() as f32).sqrt();
+            assert!(rms > 0.05, "{} decoded to near silence ({})", name, rms);
+            assert!(power(440.0) > 100.0 * power(220.0).max(power(880.0)), "{} is not a 440Hz tone", name);
+        }
+    }
+
+    #[test]
+    fn test_load_audio_file_rejects_unsupported_input() {
+        let handler = AudioFileHandler::new(DebugConfig::default());
+        let dir = std::env::temp_dir();
+        
+        let text_path = dir.join(format!("voice_to_text_notes_{}.txt", std::process::id()));
+        std::fs::write(&text_path, b"definitely not audio").unwrap();
+        let result = handler.load_audio_file(text_path.to_str().unwrap());
+        std::fs::remove_file(&text_path).ok();
+        assert!(matches!(result, Err(VoiceError::UnsupportedAudioFormat(_))));
+        
+        // Opus is recognised even when this build can't decode it
+        #[cfg(not(feature = "opus"))]
+        {
+            let mut opus_header = b"OggS\x00\x02".to_vec();
+            opus_header.resize(28, 0);
+            opus_header.extend_from_slice(b"OpusHead");
+            let opus_path = dir.join(format!("voice_to_text_memo_{}.opus", std::process::id()));
+            std::fs::write(&opus_path, &opus_header).unwrap();
+            let result = handler.load_audio_file(opus_path.to_str().unwrap());
+            std::fs::remove_file(&opus_path).ok();
+            assert!(matches!(result, Err(VoiceError::UnsupportedCodec(_))));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transcription_options() {
+        let defaults = TranscriptionOptions::default();
+        assert_eq!(defaults.language(), None);
+        assert!(!defaults.translate);
+
+        // "auto" and blank values fall back to detection
+        let auto = TranscriptionOptions { language: Some("auto".to_string()), ..Default::default() };
+        assert_eq!(auto.language(), None);
+        let french = TranscriptionOptions { language: Some(" fr ".to_string()), ..Default::default() };
+        assert_eq!(french.language(), Some("fr"));
+
+        let mut service = VoiceToTextService::new();
+        let options = TranscriptionOptions {
+            language: Some("de".to_string()),
+            translate: true,
+            initial_prompt: Some("Besprechung mit Frau Müller".to_string()),
+            ..Default::default()
+        };
+        service.set_transcription_options(options.clone());
+        assert_eq!(service.get_transcription_options(), &options);
+
+        // Without a model the options are accepted and the placeholder is returned
+        let audio = AudioBuffer::new(vec![0.1; 100], DEFAULT_SAMPLE_RATE);
+        let result = service.transcribe_audio_with_options(audio, &options).await.unwrap();
+        assert!(result.text.contains("model not loaded"));
+    }
+
+    #[test]
+    fn test_decoding_options_fallback() {
+        let defaults = DecodingOptions::default();
+        assert_eq!(defaults.strategy, DecodingStrategy::Greedy { best_of: 1 });
+        assert!(defaults.validate().is_ok());
+
+        // Reference thresholds; whisper.cpp steps through 0.0, 0.2, ..., 1.0 for a failed window
+        assert_eq!(defaults.temperature_increment, 0.2);
+        assert_eq!(defaults.entropy_threshold, 2.4);
+        assert_eq!(defaults.logprob_threshold, -1.0);
+        let no_fallback = DecodingOptions { temperature: 0.4, temperature_increment: 0.0, ..Default::default() };
+        assert!(no_fallback.validate().is_ok());
+        let negative_step = DecodingOptions { temperature_increment: -0.2, ..Default::default() };
+        assert!(matches!(negative_step.validate(), Err(VoiceError::InvalidOptions(_))));
+        let nan_entropy = DecodingOptions { entropy_threshold: f32::NAN, ..Default::default() };
+        assert!(matches!(nan_entropy.validate(), Err(VoiceError::InvalidOptions(_))));
+        let infinite_logprob = DecodingOptions { logprob_threshold: f32::NEG_INFINITY, ..Default::default() };
+        assert!(matches!(infinite_logprob.validate(), Err(VoiceError::InvalidOptions(_))));
+
+        let beam = DecodingOptions { strategy: DecodingStrategy::BeamSearch { beam_size: 0, patience: -1.0 }, ..Default::default() };
+        assert!(matches!(beam.validate(), Err(VoiceError::InvalidOptions(_))));
+        let too_hot = DecodingOptions { temperature: 1.5, ..Default::default() };
+        assert!(matches!(too_hot.validate(), Err(VoiceError::InvalidOptions(_))));
+    }
+
+    #[test]
+    fn test_words_from_tokens() {
+        use transcription::{words_from_tokens, TokenTiming};
+
+        let token = |text: &[u8], start: f32, end: f32, probability: f32| TokenTiming {
+            bytes: text.to_vec(), start, end, probability,
+        };
+        // "é" is split across two tokens, and punctuation joins the preceding word
+        let tokens = vec![
+            token(b" Caf", 0.0, 0.3, 0.9),
+            token(&[0xC3], 0.3, 0.35, 0.7),
+            token(&[0xA9], 0.35, 0.4, 0.8),
+            token(b" ouvert", 0.5, 0.9, 0.4),
+            token(b".", 0.9, 1.0, 0.6),
+        ];
+        let words = words_from_tokens(&tokens);
+
+        assert_eq!(words.len(), 2);
+        assert_eq!(words[0].word, "Café");
+        assert_eq!((words[0].start, words[0].end), (0.0, 0.4));
+        assert!((words[0].probability - 0.8).abs() < 1e-6);
+        assert_eq!(words[1].word, "ouvert.");
+        assert_eq!((words[1].start, words[1].end), (0.5, 1.0));
+        assert!(words_from_tokens(&[]).is_empty());
+
+        // Words only appear in JSON when they were requested
+        let mut segment = Segment { start: 0.0, end: 1.0, text: " Café ouvert.".to_string(), no_speech_prob: 0.0, words: vec![] };
+        assert!(!serde_json::to_string(&segment).unwrap().contains("words"));
+        segment.words = words;
+        assert!(serde_json::to_string(&segment).unwrap().contains("\"probability\""));
+    }
+
+    #[test]
+    fn test_stream_state_commits_stable_segments() {
+        let segment = |start: f32, end: f32, text: &str| Segment {
+            start, end, text: text.to_string(), no_speech_prob: 0.0, words: vec![],
+        };
+        let (events, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let mut stream = StreamState::new(StreamingOptions::default(), events);
+        let rate = 16000;
+
+        // Two segments in a 3s window: the first is followed by speech, so it becomes final
+        let window = Transcription::from_segments(vec![segment(0.0, 1.5, " Bonjour."), segment(1.5, 3.0, " Comment")], Some("fr".to_string()), 3.0);
+        stream.apply(&window, 3 * rate as usize, rate, false);
+        assert_eq!(stream.committed_samples(), (1.5 * rate as f32) as usize);
+        assert_eq!(receiver.try_recv().unwrap(), StreamEvent::Final(segment(0.0, 1.5, " Bonjour.")));
+        assert_eq!(receiver.try_recv().unwrap(), StreamEvent::Partial(segment(1.5, 3.0, " Comment")));
+
+        // The next window starts at 1.5s; flushing commits everything on the recording's timeline
+        let window = Transcription::from_segments(vec![segment(0.0, 2.0, " Comment allez-vous?")], None, 2.0);
+        stream.apply(&window, 2 * rate as usize, rate, true);
+        assert_eq!(receiver.try_recv().unwrap(), StreamEvent::Final(segment(1.5, 3.5, " Comment allez-vous?")));
+        assert!(receiver.try_recv().is_err());
+
+        let result = stream.finish(3.5);
+        assert_eq!(result.text, "Bonjour. Comment allez-vous?");
+        assert_eq!(result.segments.len(), 2);
+        assert_eq!(result.language.as_deref(), Some("fr"));
+    }
+
+    #[tokio::test]
+    async fn test_input_device_selection() {
+        let defaults = ListenOptions::default();
+        assert_eq!(defaults.timeout_ms, DEFAULT_TIMEOUT_MS);
+        assert_eq!(defaults.silence_timeout_ms, DEFAULT_SILENCE_TIMEOUT_MS);
+        assert!(defaults.auto_stop);
+        assert_eq!(defaults.device, None);
+
+        let mut service = VoiceToTextService::new();
+        assert_eq!(service.get_input_device(), None);
+        service.set_input_device(Some("no such device 1234".to_string()));
+        assert_eq!(service.get_input_device(), Some("no such device 1234"));
+
+        // An unknown device fails to start and leaves the service idle
+        let result = service.start_listening().await;
+        assert!(result.is_err());
+        assert!(!service.is_recording());
+
+        // Listing may fail without an audio system, but must not panic
+        if let Ok(devices) = service.list_input_devices() {
+            assert!(devices.iter().filter(|d| d.is_default).count() <= 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_signal_source_auto_stop() {
+        let engine = MockEngine::new().then_text("Generated speech.");
+        let service = VoiceToTextService::new_with_engine(engine.clone(), DebugConfig::default());
+        // The speaker starts a moment after the recording, as the noise floor is set from its start
+        let signal = SignalSource::new(16000).silence(300).tone(440.0, 0.5, 600).noise(0.2, 200);
+        assert_eq!(signal.audio().len(), 17600);
+        service.set_audio_source(signal).unwrap();
+
+        let listen = ListenOptions { timeout_ms: 5000, silence_timeout_ms: 300, pre_roll_ms: 0, ..Default::default() };
+        let options = TranscriptionOptions::default();
+        let started = Instant::now();
+        let swap_while_recording = async {
+            sleep(Duration::from_millis(200)).await;
+            service.set_audio_source(SignalSource::new(8000))
+        };
+        let (transcription, swapped) = tokio::join!(
+            service.start_listening_with_options(&listen, &options),
+            swap_while_recording,
+        );
+        assert!(swapped.is_err(), "the source cannot change during a recording");
+        let transcription = transcription.unwrap();
+
+        // The generated speech ends after 1.1s, so trailing silence stops the recording well before the timeout
+        assert!(started.elapsed() < Duration::from_millis(3000));
+        assert_eq!(transcription.text, "Generated speech.");
+        assert!(!service.is_recording());
+        let duration = service.recording_status().duration_seconds;
+        assert!(duration >= 1.3, "recorded {:.2}s", duration);
+        // Without pre-roll, the silence before the speaker is not transcribed
+        assert_eq!(engine.calls()[0].sample_count, (duration * 16000.0).round() as usize - 4800);
+    }
+
+    #[test]
+    fn test_vad_adapts_to_steady_noise() {
+        // A noisy office: constant noise well above the fixed silence threshold
+        let office = SignalSource::new(16000).noise(0.05, 4000);
+        let samples = &office.audio().samples;
+
+        let mut threshold = VoiceActivityDetector::new(VadOptions { mode: VadMode::Threshold, ..Default::default() }, 16000);
+        threshold.process(samples);
+        assert!(threshold.is_speech(), "the fixed threshold never hears silence here");
+
+        let mut vad = VoiceActivityDetector::new(VadOptions::default(), 16000);
+        vad.process(samples);
+        assert_eq!(vad.speech_onset(), None, "noise heard from the start is the background");
+        assert!((vad.noise_floor_db() - vad.level_db()).abs() < 3.0);
+
+        // Noise that starts in a quiet room sounds like speech at first, then becomes the floor
+        let air_conditioner = SignalSource::new(16000).noise(0.001, 1000).noise(0.05, 12000);
+        let mut vad = VoiceActivityDetector::new(VadOptions::default(), 16000);
+        vad.process(&air_conditioner.audio().samples);
+        assert_eq!(vad.speech_onset(), Some(16000));
+        assert!(!vad.is_speech(), "steady noise became the floor");
+    }
+
+    #[test]
+    fn test_vad_keeps_long_speech_without_pauses() {
+        // Speech without a single pause for longer than the noise floor window
+        let signal = SignalSource::new(16000).noise(0.001, 1000).tone(300.0, 0.3, 6500).noise(0.001, 1000);
+        let samples = &signal.audio().samples;
+        let speech_end = 16000 + 6500 * 16;
+        assert!(speech_end - 16000 > VAD_NOISE_WINDOW_MS as usize * 16);
+
+        let mut vad = VoiceActivityDetector::new(VadOptions::default(), 16000);
+        assert!(vad.process(&samples[..speech_end]), "speech ended mid-utterance");
+        assert_eq!(vad.speech_onset(), Some(16000));
+        assert!(vad.level_db() - vad.noise_floor_db() > 20.0, "the speaker became the floor");
+        assert!(!vad.process(&samples[speech_end..]), "speech ends once the room is quiet again");
+    }
+
+    #[test]
+    fn test_vad_hears_quiet_speaker_and_ignores_clicks() {
+        // A quiet room, a quiet speaker below the fixed threshold, then the room again
+        let signal = SignalSource::new(16000).noise(0.001, 1000).tone(300.0, 0.01, 800).noise(0.001, 1000);
+        let samples = &signal.audio().samples;
+
+        let mut threshold = VoiceActivityDetector::new(VadOptions { mode: VadMode::Threshold, ..Default::default() }, 16000);
+        threshold.process(samples);
+        assert!(!threshold.speech_detected());
+
+        let mut vad = VoiceActivityDetector::new(VadOptions::default(), 16000);
+        assert!(!vad.process(&samples[..16000]));
+        assert!(vad.process(&samples[16000..28000]));
+        assert!(vad.speech_detected());
+        assert_eq!(vad.speech_onset(), Some(16000));
+        assert!(!vad.process(&samples[28000..]), "speech ends once the room is quiet again");
+
+        // 60ms is shorter than the minimum speech duration
+        let click = SignalSource::new(16000).noise(0.001, 500).tone(1000.0, 0.5, 60).noise(0.001, 500);
+        let mut vad = VoiceActivityDetector::new(VadOptions::default(), 16000);
+        vad.process(&click.audio().samples);
+        assert!(!vad.speech_detected());
+    }
+
+    #[test]
+    fn test_room_tone_analysis() {
+        // Loud, steady noise on a biased input that clips once
+        let mut samples: Vec<f32> = SignalSource::new(16000).noise(0.05, 2000).audio().samples.iter().map(|s| s + 0.02).collect();
+        samples[1000..1064].fill(1.0);
+        let report = calibration::analyze_room_tone(&AudioBuffer::new(samples, 16000), &VadOptions::default()).unwrap();
+
+        // Uniform noise of amplitude 0.05 has an RMS level of about -31 dBFS
+        assert!((report.noise_floor_db + 31.0).abs() < 1.5, "noise floor {:.1}", report.noise_floor_db);
+        assert!((report.dc_offset - 0.02).abs() < 0.005);
+        assert_eq!(report.clipped_samples, 64);
+        assert_eq!(report.peak_db, 0.0);
+        assert_eq!(report.warnings.len(), 3, "{:?}", report.warnings);
+
+        let vad = &report.recommended;
+        assert_eq!(vad.initial_noise_floor_db, report.noise_floor_db);
+        assert!(vad.onset_db > vad.offset_db);
+        assert!(vad.threshold > 0.028, "fixed threshold must sit above the noise");
+
+        let too_short = AudioBuffer::new(vec![0.0; 4000], 16000);
+        assert!(matches!(calibration::analyze_room_tone(&too_short, &VadOptions::default()), Err(VoiceError::AudioTooShort { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_calibration_profile_round_trip() {
+        let profile_path = std::env::temp_dir().join(format!("voice_to_text_profile_{}/profile.json", std::process::id()));
+        let mut service = VoiceToTextService::new();
+        service.set_profile_path(Some(profile_path.clone()));
+        service.set_vad_options(VadOptions { mode: VadMode::Threshold, ..Default::default() });
+        service.set_audio_source(SignalSource::new(16000).noise(0.002, 2000)).unwrap();
+
+        let report = service.calibrate(700, None, true).await.unwrap();
+        assert!(report.warnings.is_empty(), "{:?}", report.warnings);
+        assert_eq!(report.profile_path.as_ref(), Some(&profile_path));
+        assert!(!service.is_recording());
+
+        // The recommendation is in effect now and for later sessions, keeping the chosen mode
+        let vad = service.get_vad_options();
+        assert_eq!(vad.mode, VadMode::Threshold);
+        assert_eq!(vad, report.recommended);
+        let profile = CalibrationProfile::load(&profile_path).unwrap().unwrap();
+        assert_eq!(profile.vad, vad);
+
+        assert!(CalibrationProfile::load(&profile_path.with_file_name("missing.json")).unwrap().is_none());
+        let _ = std::fs::remove_dir_all(profile_path.parent().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_listen_trims_silence_before_speech() {
+        let engine = MockEngine::new();
+        let service = VoiceToTextService::new_with_engine(engine.clone(), DebugConfig::default());
+        service.set_audio_source(SignalSource::new(16000).silence(1000).tone(300.0, 0.3, 600)).unwrap();
+
+        let listen = ListenOptions { silence_timeout_ms: 300, ..Default::default() };
+        service.start_listening_with_options(&listen, &TranscriptionOptions::default()).await.unwrap();
+
+        // Speech starts at 1s; with 300ms of pre-roll the first 0.7s never reach the engine
+        let duration = service.recording_status().duration_seconds;
+        let sent = engine.calls()[0].sample_count as f32 / 16000.0;
+        assert!((duration - sent - 0.7).abs() < 0.01, "recorded {:.2}s, transcribed {:.2}s", duration, sent);
+    }
+
+    #[tokio::test]
+    async fn test_listen_gives_up_without_speech() {
+        let engine = MockEngine::new();
+        let service = VoiceToTextService::new_with_engine(engine.clone(), DebugConfig::default());
+        service.set_audio_source(SignalSource::new(16000).noise(0.0005, 100)).unwrap();
+
+        let listen = ListenOptions { silence_timeout_ms: 100, onset_timeout_ms: 600, ..Default::default() };
+        let started = Instant::now();
+        let transcription = service.start_listening_with_options(&listen, &TranscriptionOptions::default()).await.unwrap();
+
+        // Waiting for speech does not use the silence timeout, and the silence is never transcribed
+        let elapsed = started.elapsed();
+        assert!(elapsed >= Duration::from_millis(600) && elapsed < Duration::from_millis(1500), "{:?}", elapsed);
+        assert_eq!(transcription.text, "No speech detected in audio");
+        assert!(transcription.segments.is_empty());
+        assert!(transcription.duration > 0.5, "recorded {:.2}s", transcription.duration);
+        assert!(engine.calls().is_empty());
+
+        // Nor while streaming
+        let (events, _received) = tokio::sync::mpsc::unbounded_channel();
+        let streaming = StreamingOptions { interval_ms: 100, ..Default::default() };
+        let transcription = service
+            .start_streaming_with_options(&listen, &TranscriptionOptions::default(), &streaming, events)
+            .await
+            .unwrap();
+        assert_eq!(transcription.text, "No speech detected in audio");
+        assert!(engine.calls().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_streaming_trims_silence_before_speech() {
+        let engine = MockEngine::new();
+        let service = VoiceToTextService::new_with_engine(engine.clone(), DebugConfig::default());
+        service.set_audio_source(SignalSource::new(16000).silence(1000).tone(300.0, 0.3, 600)).unwrap();
+
+        let listen = ListenOptions { silence_timeout_ms: 300, ..Default::default() };
+        let streaming = StreamingOptions { interval_ms: 100, ..Default::default() };
+        let (events, _received) = tokio::sync::mpsc::unbounded_channel();
+        let transcription = service
+            .start_streaming_with_options(&listen, &TranscriptionOptions::default(), &streaming, events)
+            .await
+            .unwrap();
+
+        // Speech starts at 1s; with 300ms of pre-roll no window includes the first 0.7s
+        let longest = engine.calls().iter().map(|c| c.sample_count).max().unwrap();
+        assert!(longest as f32 / 16000.0 <= transcription.duration - 0.7 + 0.01, "sent {} of {:.2}s", longest, transcription.duration);
+        assert!((transcription.segments[0].start - 0.7).abs() < 0.01, "{:?}", transcription.segments);
+    }
+
+    #[tokio::test]
+    async fn test_streaming_auto_stop_does_not_wait_for_partials() {
+        let engine = MockEngine::new().with_latency(Duration::from_millis(1500));
+        let service = VoiceToTextService::new_with_engine(engine.clone(), DebugConfig::default());
+        service.set_audio_source(SignalSource::new(16000).silence(200).tone(300.0, 0.3, 600)).unwrap();
+
+        let listen = ListenOptions { silence_timeout_ms: 300, ..Default::default() };
+        let streaming = StreamingOptions { interval_ms: 200, ..Default::default() };
+        let (events, _received) = tokio::sync::mpsc::unbounded_channel();
+        let transcription = service
+            .start_streaming_with_options(&listen, &TranscriptionOptions::default(), &streaming, events)
+            .await
+            .unwrap();
+
+        // Speech ends at 0.8s; a partial decode still running then must not hold the recording open
+        assert!(transcription.duration < 1.6, "recorded {:.2}s", transcription.duration);
+        assert!(engine.calls().len() >= 2);
+    }
+
+    #[tokio::test]
+    async fn test_continuous_listening_transcribes_each_utterance() {
+        let engine = MockEngine::new()
+            .with_latency(Duration::from_millis(300))
+            .then_text("First sentence.")
+            .then_text("Second sentence.");
+        let service = VoiceToTextService::new_with_engine(engine.clone(), DebugConfig::default());
+        let signal = SignalSource::new(16000).silence(500).tone(300.0, 0.3, 600).silence(800).tone(300.0, 0.3, 600);
+        service.set_audio_source(signal).unwrap();
+
+        let stopper = service.clone();
+        tokio::spawn(async move {
+            sleep(Duration::from_millis(3000)).await;
+            stopper.stop_continuous().unwrap();
+        });
+
+        let listen = ListenOptions { silence_timeout_ms: 400, pre_roll_ms: 100, ..Default::default() };
+        let (utterances, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let sent = service.listen_continuous(&listen, &TranscriptionOptions::default(), utterances).await.unwrap();
+        assert_eq!(sent, 2);
+        assert!(!service.is_recording());
+        assert!(service.stop_continuous().is_err());
+
+        let mut heard = Vec::new();
+        while let Some(event) = receiver.recv().await {
+            match event {
+                ContinuousEvent::Utterance(utterance) => heard.push(utterance),
+                ContinuousEvent::Error { error, .. } => panic!("unexpected error event: {}", error),
+            }
+        }
+        let [first, second] = <[Utterance; 2]>::try_from(heard).unwrap();
+        assert_eq!((first.index, first.text.as_str()), (0, "First sentence."));
+        assert_eq!((second.index, second.text.as_str()), (1, "Second sentence."));
+
+        // Each utterance starts with the pre-roll and ends shortly after the tone
+        assert!((first.start - 0.4).abs() < 0.05, "first utterance starts at {:.2}s", first.start);
+        assert!(first.end > 1.1 && first.end < 1.5, "first utterance ends at {:.2}s", first.end);
+        assert!((second.start - 1.8).abs() < 0.05, "second utterance starts at {:.2}s", second.start);
+        assert_eq!(second.segments[0].start, second.start);
+
+        let calls = engine.calls();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].sample_count, ((first.end - first.start) * 16000.0).round() as usize);
+    }
+
+    #[tokio::test]
+    async fn test_continuous_listening_reports_failed_utterances() {
+        let engine = MockEngine::new()
+            .then_error(VoiceError::WhisperTranscription("decoder failed".to_string()))
+            .then_text("Second sentence.");
+        let service = VoiceToTextService::new_with_engine(engine, DebugConfig::default());
+        let signal = SignalSource::new(16000).silence(500).tone(300.0, 0.3, 600).silence(800).tone(300.0, 0.3, 600);
+        service.set_audio_source(signal).unwrap();
+
+        let stopper = service.clone();
+        tokio::spawn(async move {
+            sleep(Duration::from_millis(3000)).await;
+            stopper.stop_continuous().unwrap();
+        });
+
+        let listen = ListenOptions { silence_timeout_ms: 400, pre_roll_ms: 100, ..Default::default() };
+        let (events, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let sent = service.listen_continuous(&listen, &TranscriptionOptions::default(), events).await.unwrap();
+        assert_eq!(sent, 1);
+
+        // The failed utterance is reported where it was heard, and the next one still counts from 0
+        match receiver.recv().await.unwrap() {
+            ContinuousEvent::Error { start, error, .. } => {
+                assert!((start - 0.4).abs() < 0.05, "failed utterance starts at {:.2}s", start);
+                assert!(error.contains("decoder failed"), "{}", error);
+            }
+            other => panic!("expected an error event, got {:?}", other),
+        }
+        match receiver.recv().await.unwrap() {
+            ContinuousEvent::Utterance(utterance) => assert_eq!((utterance.index, utterance.text.as_str()), (0, "Second sentence.")),
+            other => panic!("expected an utterance, got {:?}", other),
+        }
+        assert!(receiver.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_continuous_listening_drops_utterances_when_behind() {
+        let engine = MockEngine::new().with_latency(Duration::from_secs(10));
+        let service = VoiceToTextService::new_with_engine(engine, DebugConfig::default());
+        let mut signal = SignalSource::new(16000).silence(300);
+        for _ in 0..MAX_PENDING_UTTERANCES + 3 {
+            signal = signal.tone(300.0, 0.3, 150).silence(250);
+        }
+        service.set_audio_source(signal).unwrap();
+
+        let canceller = service.clone();
+        tokio::spawn(async move {
+            sleep(Duration::from_millis(5000)).await;
+            canceller.cancellation_token().cancel();
+        });
+
+        // One utterance is being transcribed and the queue is full, so the last ones are dropped;
+        // cancelling drops the queued ones without a word
+        let vad = VadOptions { hangover_ms: 0, ..Default::default() };
+        let listen = ListenOptions { silence_timeout_ms: 100, pre_roll_ms: 50, vad: Some(vad), ..Default::default() };
+        let (events, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let result = service.listen_continuous(&listen, &TranscriptionOptions::default(), events).await;
+        assert!(matches!(result, Err(VoiceError::Cancelled)));
+
+        let mut dropped = 0;
+        while let Some(event) = receiver.recv().await {
+            match event {
+                ContinuousEvent::Error { error, .. } if error.contains("fell behind") => dropped += 1,
+                other => panic!("unexpected event {:?}", other),
+            }
+        }
+        assert!(dropped >= 1, "no utterance was dropped");
+    }
+
+    /// How long three transcriptions started together take to finish
+    async fn transcribe_three_at_once(service: &VoiceToTextService) -> Duration {
+        let audio = AudioBuffer::new(vec![0.1; 16000], 16000);
+        let started = Instant::now();
+        let (a, b, c) = tokio::join!(
+            service.transcribe_audio(audio.clone()),
+            service.transcribe_audio(audio.clone()),
+            service.transcribe_audio(audio),
+        );
+        assert!(a.is_ok() && b.is_ok() && c.is_ok());
+        started.elapsed()
+    }
+
+    #[tokio::test]
+    async fn test_transcription_workers_run_concurrently() {
+        let engine = MockEngine::new().with_latency(Duration::from_millis(300));
+        let mut service = VoiceToTextService::new_with_engine(engine.clone(), DebugConfig::default());
+
+        // One worker: the transcriptions queue up
+        assert_eq!(service.get_transcription_workers(), 1);
+        let elapsed = transcribe_three_at_once(&service).await;
+        assert!(elapsed >= Duration::from_millis(900), "{:?}", elapsed);
+
+        // Three workers: they overlap on the blocking pool
+        service.set_transcription_workers(3).unwrap();
+        let elapsed = transcribe_three_at_once(&service).await;
+        assert!(elapsed < Duration::from_millis(800), "{:?}", elapsed);
+        assert_eq!(engine.calls().len(), 6);
+        assert_eq!(engine.workers(), Some(3));
+
+        assert!(matches!(service.set_transcription_workers(0), Err(VoiceError::InvalidOptions(_))));
+        assert_eq!(engine.workers(), Some(3));
+    }
+
+    #[test]
+    fn test_state_pool_keeps_one_state_per_worker() {
+        let pool = whisper::StatePool::new(3);
+        let created = std::cell::Cell::new(0);
+        let create = || {
+            created.set(created.get() + 1);
+            Ok(created.get())
+        };
+
+        // Three transcriptions at once need three states, which are then reused
+        let states: Vec<_> = (0..3).map(|_| pool.take(create).unwrap()).collect();
+        states.into_iter().for_each(|state| pool.give_back(state));
+        assert_eq!(pool.idle_count(), 3);
+        let state = pool.take(create).unwrap();
+        assert_eq!(created.get(), 3);
+
+        // With one worker left, the idle states beyond it are freed, also when returned later
+        pool.set_capacity(1);
+        assert_eq!(pool.idle_count(), 1);
+        pool.give_back(state);
+        assert_eq!(pool.idle_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_priority_semaphore_serves_highest_priority_first() {
+        let slots = PrioritySemaphore::new(1);
+        let held = slots.acquire(Priority::Low).await.unwrap();
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut waiters = Vec::new();
+        for priority in [Priority::Low, Priority::Normal, Priority::Interactive, Priority::High, Priority::Normal] {
+            let (slots, order) = (slots.clone(), Arc::clone(&order));
+            waiters.push(tokio::spawn(async move {
+                let _slot = slots.acquire(priority).await.unwrap();
+                order.lock().unwrap().push(priority);
+            }));
+            sleep(Duration::from_millis(10)).await;
+        }
+
+        // A caller that stops waiting gives up its place instead of swallowing the slot
+        assert!(tokio::time::timeout(Duration::from_millis(10), slots.acquire(Priority::Interactive)).await.is_err());
+
+        drop(held);
+        for waiter in waiters {
+            waiter.await.unwrap();
+        }
+        let order = order.lock().unwrap().clone();
+        assert_eq!(order, vec![Priority::Interactive, Priority::High, Priority::Normal, Priority::Normal, Priority::Low]);
+        assert!(slots.acquire_blocking(Priority::Low).is_ok());
+    }
+
+    /// `seconds` of a steady tone at 16kHz, written to a temporary WAV file
+    fn write_tone_wav(name: &str, seconds: u32) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("{}_{}.wav", name, std::process::id()));
+        test_common::write_wav(&path, 16000, 1, sine(220.0, 0.3, 16000, 16000 * seconds as usize));
+        path
+    }
+
+    #[tokio::test]
+    async fn test_transcription_jobs() {
+        let engine = MockEngine::new().with_latency(Duration::from_millis(100));
+        let mut service = VoiceToTextService::new_with_engine(engine, DebugConfig::default());
+        service.set_chunking_options(ChunkingOptions { chunk_ms: 2000, overlap_ms: 200, search_ms: 500 });
+        service.set_job_limits(JobLimits { max_pending: 2, ..Default::default() });
+        let jobs = JobQueue::new(service.clone());
+        let wav_path = write_tone_wav("jobs", 8);
+        let path = wav_path.to_str().unwrap();
+        let options = TranscriptionOptions::default();
+
+        let first = jobs.submit(path, options.clone(), Priority::Normal).unwrap();
+        let second = jobs.submit(path, options.clone(), Priority::Low).unwrap();
+        assert!(matches!(jobs.submit(path, options.clone(), Priority::High), Err(VoiceError::JobQueueFull { limit: 2 })));
+        assert!(matches!(jobs.submit("/nonexistent/talk.wav", options.clone(), Priority::High), Err(VoiceError::Io(_))));
+
+        // The second job waits for the only worker until it is cancelled
+        sleep(Duration::from_millis(150)).await;
+        assert_eq!(jobs.get(first).unwrap().summary.state, JobState::Running);
+        assert_eq!(jobs.get(second).unwrap().summary.state, JobState::Queued);
+        assert_eq!(jobs.cancel(second).unwrap().state, JobState::Cancelled);
+
+        // A direct call only waits for the chunk in progress, not for the whole job
+        let started = Instant::now();
+        service.transcribe_audio(AudioBuffer::new(vec![0.1; 16000], 16000)).await.unwrap();
+        assert!(started.elapsed() < Duration::from_millis(300), "{:?}", started.elapsed());
+        let status = jobs.get(first).unwrap();
+        assert_eq!(status.summary.state, JobState::Running);
+        assert!(!status.segments.is_empty());
+        assert!(status.summary.progress_percent > 0.0 && status.summary.progress_percent < 100.0);
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !jobs.get(first).unwrap().summary.state.is_finished() && Instant::now() < deadline {
+            sleep(Duration::from_millis(50)).await;
+        }
+        std::fs::remove_file(&wav_path).ok();
+
+        let status = jobs.get(first).unwrap();
+        assert_eq!(status.summary.state, JobState::Completed);
+        assert_eq!(status.summary.progress_percent, 100.0);
+        assert!(status.segments.is_empty());
+        assert_eq!(status.result.unwrap().duration, 8.0);
+
+        let states: Vec<(u64, JobState)> = jobs.list().iter().map(|job| (job.id, job.state)).collect();
+        assert_eq!(states, vec![(first, JobState::Completed), (second, JobState::Cancelled)]);
+        assert!(matches!(jobs.get(99), Err(VoiceError::JobNotFound(99))));
+    }
+
+    #[tokio::test]
+    async fn test_job_progress_within_a_chunk() {
+        let engine = MockEngine::new().with_latency(Duration::from_millis(500));
+        let service = VoiceToTextService::new_with_engine(engine, DebugConfig::default());
+        let jobs = JobQueue::new(service);
+        let wav_path = write_tone_wav("job_progress", 2);
+        let id = jobs.submit(wav_path.to_str().unwrap(), TranscriptionOptions::default(), Priority::Normal).unwrap();
+
+        // A file shorter than one chunk still reports progress while Whisper runs
+        sleep(Duration::from_millis(300)).await;
+        let status = jobs.get(id).unwrap();
+        assert_eq!(status.summary.state, JobState::Running);
+        assert!(status.summary.progress_percent > 0.0 && status.summary.progress_percent < 100.0,
+                "{}", status.summary.progress_percent);
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !jobs.get(id).unwrap().summary.state.is_finished() && Instant::now() < deadline {
+            sleep(Duration::from_millis(50)).await;
+        }
+        std::fs::remove_file(&wav_path).ok();
+        assert_eq!(jobs.get(id).unwrap().summary.progress_percent, 100.0);
+    }
+
+    #[tokio::test]
+    async fn test_cancellation_stops_recording_and_transcription() {
+        let engine = MockEngine::new().with_latency(Duration::from_secs(2));
+        let service = VoiceToTextService::new_with_engine(engine.clone(), DebugConfig::default());
+        service.set_audio_source(SignalSource::new(16000).tone(220.0, 0.5, 10000)).unwrap();
+
+        // A recording is stopped and discarded without being transcribed
+        let cancel = CancellationToken::new();
+        let listener = service.with_cancellation(cancel.clone());
+        let listen = ListenOptions { auto_stop: false, timeout_ms: 10000, ..Default::default() };
+        let recording = tokio::spawn(async move {
+            listener.start_listening_with_options(&listen, &TranscriptionOptions::default()).await
+        });
+        sleep(Duration::from_millis(300)).await;
+        let started = Instant::now();
+        cancel.cancel();
+        assert!(matches!(recording.await.unwrap(), Err(VoiceError::Cancelled)));
+        assert!(started.elapsed() < Duration::from_millis(200));
+        assert!(!service.is_recording());
+        assert!(engine.calls().is_empty());
+
+        // A transcription in progress gives up part-way through
+        let cancel = CancellationToken::new();
+        let transcriber = service.with_cancellation(cancel.clone());
+        let transcription = tokio::spawn(async move {
+            transcriber.transcribe_audio(AudioBuffer::new(vec![0.1; 16000], 16000)).await
+        });
+        sleep(Duration::from_millis(100)).await;
+        let started = Instant::now();
+        cancel.cancel();
+        assert!(matches!(transcription.await.unwrap(), Err(VoiceError::Cancelled)));
+        assert!(started.elapsed() < Duration::from_millis(200));
+        assert_eq!(engine.calls().len(), 1);
+
+        // Cancelling a clone leaves the original service working
+        assert!(!service.cancellation_token().is_cancelled());
+        let transcriber = service.with_cancellation(cancel);
+        assert!(matches!(transcriber.transcribe_audio(AudioBuffer::new(vec![0.1; 16000], 16000)).await, Err(VoiceError::Cancelled)));
+        assert_eq!(engine.calls().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_progress_events_while_recording_and_transcribing() {
+        let service = VoiceToTextService::new_with_engine(MockEngine::new(), DebugConfig::default());
+        service.set_audio_source(SignalSource::new(16000).noise(0.001, 600).tone(300.0, 0.3, 2000)).unwrap();
+
+        let (events, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let listen = ListenOptions { auto_stop: false, timeout_ms: 1300, ..Default::default() };
+        service.with_progress(events).start_listening_with_options(&listen, &TranscriptionOptions::default()).await.unwrap();
+
+        let mut recording = Vec::new();
+        let mut percents = Vec::new();
+        while let Ok(event) = receiver.try_recv() {
+            match event {
+                ProgressEvent::Recording { elapsed_seconds, voice, .. } => recording.push((elapsed_seconds, voice)),
+                ProgressEvent::Transcribing { percent } => percents.push(percent),
+            }
+        }
+
+        // Reported every half second, hearing the room first and the speaker after
+        assert!(recording.len() >= 2, "{:?}", recording);
+        assert!(recording.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert_eq!(recording[0].1, VoiceActivity::Waiting);
+        assert_eq!(recording.last().unwrap().1, VoiceActivity::Speaking);
+        assert_eq!(percents.last(), Some(&100.0));
+
+        // Sent to MCP clients as the progress message
+        let serialized = serde_json::to_value(ProgressEvent::Transcribing { percent: 50.0 }).unwrap();
+        assert_eq!(serialized, serde_json::json!({ "type": "transcribing", "percent": 50.0 }));
+    }
+
+    #[tokio::test]
+    async fn test_mock_engine_script() {
+        let engine = MockEngine::new()
+            .with_latency(Duration::from_millis(20))
+            .then_text("Bonjour tout le monde.")
+            .then_error(VoiceError::WhisperTranscription("decoder failed".to_string()));
+        let mut service = VoiceToTextService::new_with_engine(engine.clone(), DebugConfig::default());
+        service.set_transcription_options(TranscriptionOptions { language: Some("fr".to_string()), ..Default::default() });
+        assert!(service.has_model());
+
+        let audio = AudioBuffer::new(vec![0.1; 16000], 16000);
+        let transcription = service.transcribe_audio(audio.clone()).await.unwrap();
+        assert_eq!(transcription.text, "Bonjour tout le monde.");
+        assert_eq!(transcription.language.as_deref(), Some("fr"));
+        assert_eq!(transcription.segments[0].end, 1.0);
+
+        let result = service.transcribe_audio(audio.clone()).await;
+        assert!(matches!(result, Err(VoiceError::WhisperTranscription(_))));
+
+        // Once the script runs out the engine describes the audio it was given
+        let transcription = service.transcribe_audio(audio).await.unwrap();
+        assert_eq!(transcription.text, "Mock transcription of 16000 samples");
+
+        // Empty audio never reaches the engine
+        service.transcribe_audio(AudioBuffer::new(vec![], 16000)).await.unwrap();
+        let calls = engine.calls();
+        assert_eq!(calls.len(), 3);
+        assert!(calls.iter().all(|c| c.sample_count == 16000 && c.options.language.as_deref() == Some("fr")));
+        assert_eq!(engine.remaining_responses(), 0);
+
+        // An engine without a model keeps the placeholder behaviour
+        let service = VoiceToTextService::new_with_engine(MockEngine::without_model(), DebugConfig::default());
+        assert!(!service.has_model());
+        let result = service.transcribe_audio(AudioBuffer::new(vec![0.1; 100], 16000)).await.unwrap();
+        assert!(result.text.contains("model not loaded"));
+    }
+
+    #[test]
+    fn test_capture_ring_buffer() {
+        let (mut producer, mut consumer) = ring_buffer::ring_buffer(6);
+        let mut out = Vec::new();
+
+        // Capacity rounds up to 8; writes past it are dropped, never blocking the producer
+        assert_eq!(producer.push_slice(&[1.0, 2.0, 3.0, 4.0, 5.0]), 5);
+        assert_eq!(producer.push_slice(&[6.0, 7.0, 8.0, 9.0, 10.0]), 3);
+        assert_eq!(consumer.dropped(), 2);
+        assert_eq!(consumer.pop_into(&mut out), 8);
+        assert_eq!(out, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]);
+
+        // Wraps around the end of the storage
+        out.clear();
+        assert_eq!(producer.push_slice(&[11.0, 12.0, 13.0]), 3);
+        assert_eq!(consumer.pop_into(&mut out), 3);
+        assert_eq!(consumer.pop_into(&mut out), 0);
+        assert_eq!(out, vec![11.0, 12.0, 13.0]);
+
+        // A producer thread and a consumer thread see every sample in order
+        let (mut producer, mut consumer) = ring_buffer::ring_buffer(1024);
+        let writer = std::thread::spawn(move || {
+            let samples: Vec<f32> = (0..200_000).map(|i| i as f32).collect();
+            let mut written = 0;
+            while written < samples.len() {
+                let end = (written + 100).min(samples.len());
+                written += producer.push_slice(&samples[written..end]);
+            }
+        });
+        let mut received = Vec::new();
+        while received.len() < 200_000 {
+            consumer.pop_into(&mut received);
+        }
+        writer.join().unwrap();
+        assert!(received.iter().enumerate().all(|(i, &s)| s == i as f32));
+    }
+
+    /// Pushes `.0` samples the moment it starts, faster than the capture can drain them
+    struct BurstSource(usize);
+
+    impl AudioSource for BurstSource {
+        fn start(&self, _device: Option<&str>, mut sink: AudioSink) -> Result<u32> {
+            sink.push(&vec![0.1; self.0]);
+            Ok(16000)
+        }
+
+        fn stop(&self) {}
+    }
+
+    #[test]
+    fn test_capture_reports_lost_audio() {
+        let capture = AudioCapture::with_source(Box::new(BurstSource(config::CAPTURE_RING_CAPACITY + 1000)), false);
+        capture.start_capture().unwrap();
+        assert!(matches!(capture.stop_capture(), Err(VoiceError::CaptureLost(_))));
+
+        // Cancelling throws the audio away, so nothing is reported
+        capture.start_capture().unwrap();
+        assert!(capture.cancel_capture().is_ok());
+
+        let capture = AudioCapture::with_source(Box::new(BurstSource(1000)), false);
+        capture.start_capture().unwrap();
+        assert_eq!(capture.stop_capture().unwrap().len(), 1000);
+    }
+
+    #[test]
+    fn test_recording_spills_to_disk() {
+        // Room for 1000 samples in memory
+        let mut recording = RecordedAudio::new(16000, 4000);
+        let ramp: Vec<f32> = (0..2500).map(|i| i as f32).collect();
+        for chunk in ramp.chunks(300) {
+            recording.append(chunk).unwrap();
+        }
+
+        assert!(recording.is_spilled());
+        assert_eq!(recording.len(), 2500);
+        assert_eq!(recording.to_buffer().unwrap().samples, ramp);
+
+        // Reads spanning the file and the in-memory tail
+        assert_eq!(recording.read(1100, 300).unwrap().samples, ramp[1100..1400]);
+        assert_eq!(recording.read_from(2400).unwrap().samples, ramp[2400..]);
+        assert!(recording.read(3000, 10).unwrap().is_empty());
+
+        // Trimming the front applies to reads from both parts
+        recording.trim_start(1100);
+        assert_eq!(recording.len(), 1400);
+        assert_eq!(recording.read(0, 300).unwrap().samples, ramp[1100..1400]);
+        assert_eq!(recording.to_buffer().unwrap().samples, ramp[1100..]);
+        recording.trim_start(5000);
+        assert!(recording.is_empty());
+
+        let small = RecordedAudio::new(16000, 4000);
+        assert!(!small.is_spilled());
+        assert!(small.is_empty());
+    }
+
+    #[test]
+    fn test_recording_discards_old_audio() {
+        let mut recording = RecordedAudio::new(16000, 4000);
+        let ramp: Vec<f32> = (0..2600).map(|i| i as f32).collect();
+        for chunk in ramp[..2500].chunks(300) {
+            recording.append(chunk).unwrap();
+        }
+
+        // The file is only freed once everything in it can go
+        recording.discard_before(1000);
+        assert!(recording.is_spilled());
+        assert_eq!(recording.read(900, 200).unwrap().samples, ramp[900..1100]);
+
+        recording.discard_before(2450);
+        assert!(!recording.is_spilled());
+        assert_eq!(recording.len(), 2500);
+        assert_eq!(recording.read(2450, 50).unwrap().samples, ramp[2450..2500]);
+        assert_eq!(recording.read(2000, 500).unwrap().samples, ramp[2450..2500]);
+
+        // Later samples keep their indices
+        recording.append(&ramp[2500..]).unwrap();
+        assert_eq!(recording.len(), 2600);
+        assert_eq!(recording.read_from(2500).unwrap().samples, ramp[2500..]);
+    }
+
+    /// In-memory spill storage whose writes fail once `fail_after` more bytes have been written
+    struct FailingSpill {
+        file: std::io::Cursor<Vec<u8>>,
+        fail_after: Arc<Mutex<Option<usize>>>,
+    }
+
+    impl std::io::Write for FailingSpill {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            let mut fail_after = self.fail_after.lock().unwrap();
+            let len = match *fail_after {
+                Some(0) => {
+                    *fail_after = None;
+                    return Err(std::io::Error::new(std::io::ErrorKind::StorageFull, "disk full"));
+                }
+                Some(ref mut remaining) => {
+                    let len = buf.len().min(*remaining);
+                    *remaining -= len;
+                    len
+                }
+                None => buf.len(),
+            };
+            self.file.write(&buf[..len])
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl std::io::Read for FailingSpill {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.file.read(buf)
+        }
+    }
+
+    impl std::io::Seek for FailingSpill {
+        fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+            self.file.seek(pos)
+        }
+    }
+
+    #[test]
+    fn test_recording_recovers_from_failed_spill() {
+        let fail_after = Arc::new(Mutex::new(None));
+        let spill = FailingSpill { file: Default::default(), fail_after: fail_after.clone() };
+        // Room for 4 samples in memory
+        let mut recording = RecordedAudio::new(16000, 16).with_spill_storage(spill);
+        let ramp: Vec<f32> = (0..9).map(|i| i as f32).collect();
+        recording.append(&ramp[..4]).unwrap();
+        assert!(recording.is_spilled());
+
+        // The disk fills up half-way through the second spill
+        *fail_after.lock().unwrap() = Some(8);
+        assert!(recording.append(&ramp[4..8]).is_err());
+        assert_eq!(recording.to_buffer().unwrap().samples, ramp[..8]);
+
+        // The retry overwrites the partial write instead of appending after it
+        recording.append(&ramp[8..]).unwrap();
+        assert_eq!(recording.len(), 9);
+        assert_eq!(recording.to_buffer().unwrap().samples, ramp);
+        assert_eq!(recording.read(3, 4).unwrap().samples, ramp[3..7]);
+    }
+
+    /// 70 seconds of steady "speech" at 1kHz with short pauses at 27s and 54s
+    fn recording_with_pauses(memory_limit_bytes: usize) -> RecordedAudio {
+        let mut samples = vec![0.1; 70_000];
+        samples[27_000..27_200].fill(0.0);
+        samples[54_000..54_200].fill(0.0);
+        let mut recording = RecordedAudio::new(1000, memory_limit_bytes);
+        recording.append(&samples).unwrap();
+        recording
+    }
+
+    #[tokio::test]
+    async fn test_spilled_recording_transcribed_in_chunks() {
+        let engine = MockEngine::new().then_text("First.").then_text("Second.").then_text("Third.");
+        let service = VoiceToTextService::new_with_engine(engine.clone(), DebugConfig::default());
+
+        // Almost all of it on disk
+        let recording = recording_with_pauses(4096);
+        assert!(recording.is_spilled());
+
+        let progress = Arc::new(Mutex::new(Vec::new()));
+        let reported = Arc::clone(&progress);
+        let transcription = service
+            .transcribe_recording_with_progress(recording, &TranscriptionOptions::default(), move |p| {
+                reported.lock().unwrap().push((p.chunk, p.processed_seconds, p.segments.len()));
+            })
+            .await
+            .unwrap();
+        assert_eq!(transcription.text, "First. Second. Third.");
+        assert_eq!(transcription.duration, 70.0);
+
+        // Each chunk is cut in the middle of the first quiet frame and the next starts 1s earlier
+        let chunks: Vec<usize> = engine.calls().iter().map(|c| c.sample_count).collect();
+        assert_eq!(chunks, vec![27_050, 28_000, 16_950]);
+        let starts: Vec<f32> = transcription.segments.iter().map(|s| s.start).collect();
+        assert_eq!(starts.len(), 3);
+        assert!((starts[1] - 26.05).abs() < 1e-3 && (starts[2] - 53.05).abs() < 1e-3);
+        assert_eq!(transcription.segments[2].end, 70.0);
+
+        let progress = progress.lock().unwrap().clone();
+        assert_eq!(progress, vec![(1, 27.05, 1), (2, 54.05, 1), (3, 70.0, 1)]);
+
+        // Without a model the placeholder is built without loading the audio
+        let service = VoiceToTextService::new();
+        let transcription = service.transcribe_recording(recording_with_pauses(4096), &TranscriptionOptions::default()).await.unwrap();
+        assert!(transcription.text.contains("model not loaded"));
+    }
+
+    #[tokio::test]
+    async fn test_chunk_seams_drop_repeated_words() {
+        let engine = MockEngine::new()
+            .then_text(" one two three four.")
+            .then_text(" three four. five six.")
+            .then_text(" five six. seven eight.");
+        let service = VoiceToTextService::new_with_engine(engine, DebugConfig::default());
+
+        let transcription = service.transcribe_recording(recording_with_pauses(1 << 20), &TranscriptionOptions::default()).await.unwrap();
+        assert_eq!(transcription.text, "one two three four. five six. seven eight.");
+    }
+
+    #[test]
+    fn test_stitched_seams_keep_word_boundaries() {
+        let segment = |text: &str| Segment { start: 0.0, end: 1.0, text: text.to_string(), no_speech_prob: 0.0, words: vec![] };
+        let mut segments = vec![segment(" So the plan for next week"), segment(" is to ship the beta")];
+
+        // The overlap repeats "ship the beta", and the rest must still be a separate word
+        let added = chunking::stitch_segments(&mut segments, vec![segment(" ship the beta on Tuesday,"), segment(" and then we wait.")]);
+        assert_eq!(added, 2);
+        // A chunk whose first segment lacks Whisper's leading space
+        chunking::stitch_segments(&mut segments, vec![segment("Then the release.")]);
+        // A chunk that only repeats the seam before moving on
+        chunking::stitch_segments(&mut segments, vec![segment(" the release."), segment(" Questions?")]);
+
+        let transcription = Transcription::from_segments(segments, None, 4.0);
+        assert_eq!(
+            transcription.text,
+            "So the plan for next week is to ship the beta on Tuesday, and then we wait. Then the release. Questions?"
+        );
+    }
+
+    #[test]
+    fn test_chunking_options_validation() {
+        assert!(ChunkingOptions::default().validate().is_ok());
+        let options = ChunkingOptions { chunk_ms: 5000, overlap_ms: 1000, search_ms: 4000 };
+        assert!(matches!(options.validate(), Err(VoiceError::InvalidOptions(_))));
+    }
+
+    #[test]
+    fn test_transcription_from_segments() {
+        let segments = vec![
+            Segment { start: 0.0, end: 1.5, text: " Hello".to_string(), no_speech_prob: 0.01, words: vec![] },
+            Segment { start: 1.5, end: 2.75, text: " world.".to_string(), no_speech_prob: 0.02, words: vec![] },
+        ];
+        let transcription = Transcription::from_segments(segments, Some("en".to_string()), 3.0);
+        
+        assert_eq!(transcription.text, "Hello world.");
+        assert_eq!(transcription.segments.len(), 2);
+        assert_eq!(transcription.to_string(), "Hello world.");
+        
+        let json: serde_json::Value = serde_json::from_str(&transcription.to_json()).unwrap();
+        assert_eq!(json["language"], "en");
+        assert_eq!(json["segments"][1]["start"], 1.5);
+        assert_eq!(json["segments"][1]["end"], 2.75);
+        assert_eq!(json["segments"][1]["text"], " world.");
+    }
+
+    #[test]
+    fn test_output_formats_render_segments() {
+        let transcription = Transcription::from_segments(vec![
+            Segment { start: 0.0, end: 2.5, text: " Welcome to the demo.".to_string(), no_speech_prob: 0.0, words: vec![] },
+            Segment { start: 3661.25, end: 3662.0, text: " Bye.".to_string(), no_speech_prob: 0.0, words: vec![] },
+        ], Some("en".to_string()), 3662.0);
+        
+        assert_eq!(
+            OutputFormat::Srt.render(&transcription),
+            "1\n00:00:00,000 --> 00:00:02,500\nWelcome to the demo.\n\n2\n01:01:01,250 --> 01:01:02,000\nBye.\n\n"
+        );
+        assert_eq!(
+            OutputFormat::Vtt.render(&transcription),
+            "WEBVTT\n\n00:00:00.000 --> 00:00:02.500\nWelcome to the demo.\n\n01:01:01.250 --> 01:01:02.000\nBye.\n\n"
+        );
+        assert_eq!(
+            OutputFormat::Tsv.render(&transcription),
+            "start\tend\ttext\n0\t2500\tWelcome to the demo.\n3661250\t3662000\tBye.\n"
+        );
+        assert_eq!(OutputFormat::Text.render(&transcription), "Welcome to the demo. Bye.");
+        assert!(OutputFormat::Json.render(&transcription).contains("\"language\": \"en\""));
+    }
+
+    #[test]
+    fn test_output_format_parsing_and_sidecar() {
+        assert_eq!("SRT".parse::<OutputFormat>(), Ok(OutputFormat::Srt));
+        assert_eq!("webvtt".parse::<OutputFormat>(), Ok(OutputFormat::Vtt));
+        assert_eq!("txt".parse::<OutputFormat>(), Ok(OutputFormat::Text));
+        assert!("docx".parse::<OutputFormat>().is_err());
+        
+        let input = std::env::temp_dir().join(format!("voice_to_text_talk_{}.mp3", std::process::id()));
+        let transcription = Transcription::from_segments(vec![
+            Segment { start: 0.0, end: 1.0, text: " Hi".to_string(), no_speech_prob: 0.0, words: vec![] },
+        ], None, 1.0);
+        
+        let sidecar = OutputFormat::Srt.write_sidecar(input.to_str().unwrap(), &transcription).unwrap();
+        assert_eq!(sidecar, input.with_extension("srt"));
+        let contents = std::fs::read_to_string(&sidecar).unwrap();
+        std::fs::remove_file(&sidecar).ok();
+        assert!(contents.contains("00:00:00,000 --> 00:00:01,000"));
+        
+        // A sidecar must never replace the input itself
+        let txt_input = input.with_extension("txt");
+        let result = OutputFormat::Text.write_sidecar(txt_input.to_str().unwrap(), &transcription);
+        assert!(matches!(result, Err(VoiceError::Io(e)) if e.kind() == std::io::ErrorKind::AlreadyExists));
+    }
+
     #[test]
     fn test_audio_processing_pipeline_consistency() {
         let debug_config = DebugConfig::default();
         let audio_processor = AudioProcessor::new(debug_config.enabled);
         
         // Test that audio processing works consistently across all platforms
-        let test_audio = vec![0.1, -0.2, 0.3, -0.4, 0.5];
+        let test_audio = AudioBuffer::new(vec![0.1, -0.2, 0.3, -0.4, 0.5], DEFAULT_SAMPLE_RATE);
         let processed = audio_processor.prepare_for_whisper(&test_audio);
         
         assert!(processed.is_ok());
         let processed_audio = processed.unwrap();
+        assert_eq!(processed_audio.sample_rate, WHISPER_SAMPLE_RATE);
+        let processed_audio = processed_audio.samples;
         
         // Processed audio should be valid for Whisper regardless of platform
         assert!(!processed_audio.is_empty());
@@ -1,8 +1,8 @@
-use voice_to_text_mcp::{VoiceToTextService, DebugConfig};
+use voice_to_text_mcp::{VoiceToTextService, DebugConfig, ListenOptions, AudioCapture, OutputFormat, TranscriptionOptions, DecodingOptions, DecodingStrategy, StreamEvent, StreamingOptions, ChunkingOptions, ChunkProgress, ResampleQuality, VadMode, VadOptions, CalibrationProfile, JobLimits};
 use voice_to_text_mcp::mcp_server::run_mcp_server;
 use anyhow::Result;
 use std::path::PathBuf;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 
 #[derive(Parser, Debug)]
 #[command(name = "voice-to-text-mcp")]
@@ -42,14 +42,145 @@ struct Args {
     #[arg(long, default_value = "2000")]
     silence_timeout_ms: u64,
 
+    /// How long to wait for speech to begin before giving up, in milliseconds
+    #[arg(long, default_value = "5000")]
+    onset_timeout_ms: u64,
+
+    /// Audio kept before speech begins, in milliseconds; earlier silence is trimmed
+    #[arg(long, default_value = "300")]
+    pre_roll_ms: u64,
+
     /// Disable automatic stopping on silence detection
     #[arg(long)]
     no_auto_stop: bool,
+
+    /// Record from this input device (full name or part of it) instead of the system default
+    #[arg(long, value_name = "NAME")]
+    device: Option<String>,
+
+    /// List the available input devices and exit
+    #[arg(long)]
+    list_devices: bool,
+
+    /// Audio kept in memory per recording in megabytes; longer recordings spill to a temporary file
+    #[arg(long, value_name = "MB", default_value = "256")]
+    max_memory_mb: usize,
+
+    /// Voice activity detector for auto-stop: adaptive (tracks the background noise) or threshold (fixed level) [default: adaptive, or the calibrated profile's]
+    #[arg(long, value_name = "MODE")]
+    vad: Option<VadMode>,
+
+    /// Calibration profile to load at startup and save to with `calibrate`
+    /// [default: $VOICE_PROFILE or ~/.config/voice-to-text-mcp/profile.json]
+    #[arg(long, value_name = "PATH")]
+    profile: Option<PathBuf>,
+
+    /// Resampler filter quality: fast, balanced or best
+    #[arg(long, value_name = "QUALITY", default_value = "balanced")]
+    resample_quality: ResampleQuality,
+
+    /// Transcriptions run at once, e.g. for concurrent MCP requests; each needs its own Whisper state
+    #[arg(long, value_name = "N", default_value = "1")]
+    workers: usize,
+
+    /// MCP server: background transcription jobs that may be queued or running at once
+    #[arg(long, value_name = "N", default_value = "16")]
+    max_jobs: usize,
+
+    /// Longest piece of audio transcribed in one pass; longer audio is split at quiet points
+    #[arg(long, value_name = "SECONDS", default_value = "30")]
+    chunk_seconds: u64,
+
+    /// Transcribe while recording and show partial results live on stderr
+    #[arg(long, conflicts_with = "file")]
+    stream: bool,
+
+    /// Keep listening until Ctrl+C, printing each utterance as a line of JSON on stdout.
+    /// Utterances end after --silence-timeout-ms of silence and last at most --timeout-ms
+    #[arg(long, conflicts_with_all = ["file", "stream"])]
+    continuous: bool,
+
+    /// How often the live audio is transcribed with --stream, in milliseconds
+    #[arg(long, default_value = "1000")]
+    stream_interval_ms: u64,
+
+    /// Transcribe this audio file instead of recording from the microphone
+    #[arg(long, value_name = "AUDIO_FILE")]
+    file: Option<PathBuf>,
+
+    /// Output format: text, json, srt, vtt or tsv
+    #[arg(long, value_name = "FORMAT", default_value = "text")]
+    format: OutputFormat,
+
+    /// Write the result to a sidecar file next to --file (e.g. talk.mp3 -> talk.srt) instead of stdout
+    #[arg(long, requires = "file")]
+    sidecar: bool,
+
+    /// Spoken language as an ISO 639-1 code, e.g. fr or de (default: auto-detect)
+    #[arg(short, long, value_name = "LANG")]
+    language: Option<String>,
+
+    /// Translate the speech into English instead of transcribing it
+    #[arg(long)]
+    translate: bool,
+
+    /// Text to prime Whisper with, such as names or domain vocabulary
+    #[arg(long, value_name = "TEXT")]
+    initial_prompt: Option<String>,
+
+    /// Include per-word timestamps and probabilities (shown in JSON output)
+    #[arg(long)]
+    word_timestamps: bool,
+
+    /// Use beam search with this many beams instead of greedy decoding
+    #[arg(long, value_name = "N")]
+    beam_size: Option<u32>,
+
+    /// Candidates sampled per decode when the temperature is above zero (greedy decoding)
+    #[arg(long, value_name = "N", default_value = "1", conflicts_with = "beam_size")]
+    best_of: u32,
+
+    /// Initial sampling temperature between 0.0 and 1.0
+    #[arg(long, default_value = "0.0")]
+    temperature: f32,
+
+    /// Temperature added on each retry of a failed decode (0 disables fallback)
+    #[arg(long, default_value = "0.2")]
+    temperature_increment: f32,
+
+    /// Retry a 30s window when the entropy of its last tokens falls below this value (repetition loops)
+    #[arg(long, default_value = "2.4", allow_negative_numbers = true)]
+    entropy_threshold: f32,
+
+    /// Retry a 30s window when its average log-probability falls below this value
+    #[arg(long, default_value = "-1.0", allow_negative_numbers = true)]
+    logprob_threshold: f32,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Record room tone, measure the input and save recommended silence detection settings
+    Calibrate {
+        /// How long to record room tone, in milliseconds; stay quiet meanwhile
+        #[arg(long, default_value = "3000")]
+        duration_ms: u64,
+
+        /// Show the measurements without saving a profile
+        #[arg(long)]
+        no_save: bool,
+    },
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
+
+    if args.list_devices {
+        return list_devices();
+    }
     
     // Create debug configuration from CLI args and environment variables
     let env_debug = std::env::var("VOICE_DEBUG")
@@ -72,7 +203,7 @@ async fn main() -> Result<()> {
 
     
     // Create the voice service
-    let service = if let Some(ref model_path) = args.model_path {
+    let mut service = if let Some(ref model_path) = args.model_path {
         if model_path.exists() {
             match VoiceToTextService::new_with_model_and_debug(model_path.to_str().unwrap(), debug_config.clone()) {
                 Ok(service) => service,
@@ -90,6 +221,79 @@ async fn main() -> Result<()> {
         VoiceToTextService::new_with_debug(debug_config.clone())
     };
 
+    // Language, translation, prompt and decoder settings apply to every transcription, including MCP tool calls
+    let strategy = match args.beam_size {
+        Some(beam_size) => DecodingStrategy::BeamSearch { beam_size, patience: -1.0 },
+        None => DecodingStrategy::Greedy { best_of: args.best_of },
+    };
+    let transcription_options = TranscriptionOptions {
+        language: args.language.clone(),
+        translate: args.translate,
+        initial_prompt: args.initial_prompt.clone(),
+        decoding: DecodingOptions {
+            strategy,
+            temperature: args.temperature,
+            temperature_increment: args.temperature_increment,
+            entropy_threshold: args.entropy_threshold,
+            logprob_threshold: args.logprob_threshold,
+        },
+        word_timestamps: args.word_timestamps,
+        resample_quality: args.resample_quality,
+    };
+    if let Err(e) = transcription_options.decoding.validate() {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+    service.set_transcription_options(transcription_options.clone());
+    service.set_input_device(args.device.clone());
+    service.set_memory_limit(args.max_memory_mb * 1024 * 1024);
+    let chunking_options = ChunkingOptions { chunk_ms: args.chunk_seconds * 1000, ..Default::default() };
+    if let Err(e) = chunking_options.validate() {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+    service.set_chunking_options(chunking_options);
+    if let Err(e) = service.set_transcription_workers(args.workers) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+    service.set_job_limits(JobLimits { max_pending: args.max_jobs, ..Default::default() });
+
+    // Silence detection settings come from the last calibration, if any
+    let profile_path = args.profile.clone().or_else(CalibrationProfile::default_path);
+    let mut vad_options = VadOptions::default();
+    if let Some(ref path) = profile_path {
+        match CalibrationProfile::load(path) {
+            Ok(Some(profile)) => {
+                if debug_config.enabled {
+                    eprintln!("Loaded calibration profile from {} ({:.1} dBFS noise floor)", path.display(), profile.noise_floor_db);
+                }
+                vad_options = profile.vad;
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("Warning: Ignoring calibration profile: {}", e),
+        }
+    }
+    if let Some(mode) = args.vad {
+        vad_options.mode = mode;
+    }
+    service.set_vad_options(vad_options);
+    service.set_profile_path(profile_path);
+
+    if let Some(Command::Calibrate { duration_ms, no_save }) = args.command {
+        return calibrate(&service, duration_ms, args.device.as_deref(), !no_save).await;
+    }
+
+    let listen_options = ListenOptions {
+        timeout_ms: args.timeout_ms,
+        silence_timeout_ms: args.silence_timeout_ms,
+        onset_timeout_ms: args.onset_timeout_ms,
+        pre_roll_ms: args.pre_roll_ms,
+        auto_stop: !args.no_auto_stop,
+        device: args.device.clone(),
+        vad: None,
+    };
+
     // Check if running as MCP server
     if args.mcp_server {
         // Set environment variable to disable keyboard raw mode in MCP server mode
@@ -105,19 +309,155 @@ async fn main() -> Result<()> {
         std::process::exit(1);
     }
 
-    // Record audio and get transcription (blocking operation)
-    let auto_stop = !args.no_auto_stop;
+    if args.continuous {
+        return listen_continuous(&service, &listen_options, &transcription_options).await;
+    }
+
+    // Transcribe the given file, or record audio and get transcription (blocking operation)
+    let transcription = if let Some(ref file) = args.file {
+        let progress = |chunk: &ChunkProgress| {
+            eprintln!("Transcribed {:.0}s of {:.0}s ({:.0}%)", chunk.processed_seconds, chunk.total_seconds, chunk.percent());
+        };
+        match service.transcribe_audio_file_with_progress(&file.to_string_lossy(), &transcription_options, progress).await {
+            Ok(transcription) => transcription,
+            Err(e) => {
+                eprintln!("Error: Failed to transcribe {}: {}", file.display(), e);
+                std::process::exit(1);
+            }
+        }
+    } else if args.stream {
+        let streaming = StreamingOptions { interval_ms: args.stream_interval_ms, ..Default::default() };
+        let (events, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let printer = tokio::spawn(async move {
+            while let Some(event) = receiver.recv().await {
+                print_stream_event(&event);
+            }
+        });
+
+        let result = service.start_streaming_with_options(&listen_options, &transcription_options, &streaming, events).await;
+        let _ = printer.await;
+        match result {
+            Ok(transcription) => transcription,
+            Err(e) => {
+                eprintln!("Error: Failed to record audio: {}", e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        match service.start_listening_with_options(&listen_options, &transcription_options).await {
+            Ok(transcription) => transcription,
+            Err(e) => {
+                eprintln!("Error: Failed to record audio: {}", e);
+                std::process::exit(1);
+            }
+        }
+    };
+
+    if args.sidecar {
+        // --sidecar requires --file, so the input path is always present here
+        let input = args.file.as_ref().map(|f| f.to_string_lossy().to_string()).unwrap_or_default();
+        match args.format.write_sidecar(&input, &transcription) {
+            Ok(path) => eprintln!("Saved {} transcript to {}", args.format, path.display()),
+            Err(e) => {
+                eprintln!("Error: Failed to write sidecar file: {}", e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        // Print the transcription result to stdout
+        println!("{}", args.format.render(&transcription).trim_end());
+    }
     
-    match service.start_listening_with_options(args.timeout_ms, args.silence_timeout_ms, auto_stop).await {
-        Ok(transcription) => {
-            // Print the transcription result to stdout
-            println!("{}", transcription);
+    Ok(())
+}
+
+/// Record room tone for the `calibrate` command and print the report, saving the recommended settings when `save` is set
+async fn calibrate(service: &VoiceToTextService, duration_ms: u64, device: Option<&str>, save: bool) -> Result<()> {
+    eprintln!("Calibrating: stay quiet for {:.1} seconds...", duration_ms as f32 / 1000.0);
+    let report = service.calibrate(duration_ms, device, save).await?;
+
+    println!("Noise floor:    {:.1} dBFS (varies by {:.1} dB)", report.noise_floor_db, report.noise_variation_db);
+    println!("Peak level:     {:.1} dBFS", report.peak_db);
+    println!("DC offset:      {:.4}", report.dc_offset);
+    println!("Clipping:       {} samples ({:.2}%)", report.clipped_samples, report.clipping_ratio * 100.0);
+    println!("Recommended:    speech starts {:.1} dB and ends {:.1} dB above the noise floor", 
+             report.recommended.onset_db, report.recommended.offset_db);
+    for warning in &report.warnings {
+        println!("Warning: {}", warning);
+    }
+    match report.profile_path {
+        Some(path) => eprintln!("Saved calibration profile to {}", path.display()),
+        None if save => eprintln!("No profile path available; pass --profile to save the calibration"),
+        None => {}
+    }
+    Ok(())
+}
+
+/// Print every utterance as a line of JSON until Ctrl+C, for piping dictation into other tools
+async fn listen_continuous(service: &VoiceToTextService, listen: &ListenOptions, options: &TranscriptionOptions) -> Result<()> {
+    let stopper = service.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            let _ = stopper.stop_continuous();
+        }
+    });
+
+    let (utterances, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+    let printer = tokio::spawn(async move {
+        use std::io::Write;
+
+        let mut stdout = std::io::stdout();
+        while let Some(utterance) = receiver.recv().await {
+            if let Ok(line) = serde_json::to_string(&utterance) {
+                let _ = writeln!(stdout, "{}", line);
+                let _ = stdout.flush();
+            }
+        }
+    });
+
+    eprintln!("Listening continuously, press Ctrl+C to stop...");
+    let result = service.listen_continuous(listen, options, utterances).await;
+    let _ = printer.await;
+    match result {
+        Ok(count) => {
+            eprintln!("Transcribed {} utterances", count);
+            Ok(())
         }
         Err(e) => {
             eprintln!("Error: Failed to record audio: {}", e);
             std::process::exit(1);
         }
     }
-    
+}
+
+/// Print the input devices for `--list-devices`
+fn list_devices() -> Result<()> {
+    let devices = AudioCapture::list_input_devices()?;
+    if devices.is_empty() {
+        println!("No input devices found");
+    }
+    for device in devices {
+        let rates: Vec<String> = device.sample_rates.iter().map(|r| r.to_string()).collect();
+        let channels: Vec<String> = device.channels.iter().map(|c| c.to_string()).collect();
+        println!("{}{}", device.name, if device.is_default { " (default)" } else { "" });
+        println!("    sample rates: {} Hz", rates.join(", "));
+        println!("    channels: {}", channels.join(", "));
+    }
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Show streaming results on stderr: partials overwrite the current line, finals are kept
+fn print_stream_event(event: &StreamEvent) {
+    use std::io::Write;
+
+    let mut stderr = std::io::stderr();
+    match event {
+        StreamEvent::Partial(segment) => {
+            let _ = write!(stderr, "\r\x1b[2K… {}", segment.text.trim());
+        }
+        StreamEvent::Final(segment) => {
+            let _ = writeln!(stderr, "\r\x1b[2K{}", segment.text.trim());
+        }
+    }
+    let _ = stderr.flush();
+}
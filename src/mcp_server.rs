@@ -1,37 +1,169 @@
 use rmcp::{
     handler::server::{ServerHandler, tool::{ToolRouter, Parameters}},
-    model::{ServerCapabilities, ServerInfo, ListToolsResult, CallToolResult, CallToolRequestParam, PaginatedRequestParam, Content},
+    model::{ServerCapabilities, ServerInfo, ListToolsResult, CallToolResult, CallToolRequestParam, PaginatedRequestParam, Content, ProgressNotificationParam, ProgressToken},
     service::{ServiceExt, RequestContext, RoleServer},
-    tool, tool_router,
+    tool, tool_router, Peer,
 };
 use std::future::Future;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
 use schemars::JsonSchema;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::sync::atomic::{AtomicU32, Ordering};
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::task::JoinHandle;
 
-use crate::VoiceToTextService;
+use crate::{ListenOptions, VoiceError, VoiceToTextService};
+use crate::config::DEFAULT_CALIBRATION_MS;
+use crate::jobs::JobQueue;
+use crate::output_format::OutputFormat;
+use crate::progress::ProgressEvent;
+use crate::scheduler::Priority;
+use crate::streaming::{StreamEvent, StreamingOptions};
+use crate::vad::{VadMode, VadOptions};
+use crate::whisper::{DecodingOptions, DecodingStrategy, TranscriptionOptions};
 
-#[derive(Debug, Deserialize, JsonSchema)]
+/// Whisper options shared by every tool that transcribes audio
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct TranscriptionParams {
+    #[schemars(description = "Spoken language as an ISO 639-1 code, e.g. \"fr\" or \"de\" (default: auto-detect)")]
+    pub language: Option<String>,
+    #[schemars(description = "Translate the speech into English instead of transcribing it (default: false)")]
+    pub translate: Option<bool>,
+    #[schemars(description = "Text to prime the decoder with, such as names or domain vocabulary")]
+    pub initial_prompt: Option<String>,
+    #[schemars(description = "Include per-word start/end times and confidence in each JSON segment (default: false)")]
+    pub word_timestamps: Option<bool>,
+    #[schemars(description = "Use beam search with this many beams instead of greedy decoding")]
+    pub beam_size: Option<u32>,
+    #[schemars(description = "Number of candidates sampled per decode when the temperature is above zero (greedy decoding only)")]
+    pub best_of: Option<u32>,
+    #[schemars(description = "Initial sampling temperature between 0.0 and 1.0 (default: 0.0)")]
+    pub temperature: Option<f32>,
+    #[schemars(description = "Temperature added on each retry of a failed decode; 0 disables fallback (default: 0.2)")]
+    pub temperature_increment: Option<f32>,
+    #[schemars(description = "Retry a 30-second window when the entropy of its last tokens falls below this value, as happens in repetition loops (default: 2.4)")]
+    pub entropy_threshold: Option<f32>,
+    #[schemars(description = "Retry a 30-second window when its average log-probability falls below this value (default: -1.0)")]
+    pub logprob_threshold: Option<f32>,
+}
+
+impl TranscriptionParams {
+    /// Overlay the request's values on the server-wide defaults
+    pub fn to_options(&self, defaults: &TranscriptionOptions) -> TranscriptionOptions {
+        TranscriptionOptions {
+            language: self.language.clone().or_else(|| defaults.language.clone()),
+            translate: self.translate.unwrap_or(defaults.translate),
+            initial_prompt: self.initial_prompt.clone().or_else(|| defaults.initial_prompt.clone()),
+            decoding: self.to_decoding_options(&defaults.decoding),
+            word_timestamps: self.word_timestamps.unwrap_or(defaults.word_timestamps),
+            resample_quality: defaults.resample_quality,
+        }
+    }
+
+    fn to_decoding_options(&self, defaults: &DecodingOptions) -> DecodingOptions {
+        let strategy = match (self.beam_size, self.best_of) {
+            (Some(beam_size), _) => DecodingStrategy::BeamSearch { beam_size, patience: -1.0 },
+            (None, Some(best_of)) => DecodingStrategy::Greedy { best_of },
+            (None, None) => defaults.strategy,
+        };
+
+        DecodingOptions {
+            strategy,
+            temperature: self.temperature.unwrap_or(defaults.temperature),
+            temperature_increment: self.temperature_increment.unwrap_or(defaults.temperature_increment),
+            entropy_threshold: self.entropy_threshold.unwrap_or(defaults.entropy_threshold),
+            logprob_threshold: self.logprob_threshold.unwrap_or(defaults.logprob_threshold),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
 pub struct TranscribeFileRequest {
-    #[schemars(description = "Path to the audio file to transcribe")]
+    #[schemars(description = "Path to the audio file to transcribe (WAV, MP3, FLAC, Ogg Vorbis or AAC/M4A)")]
     pub file_path: String,
+    #[schemars(description = "Output format: json, text, srt, vtt or tsv (default: json)")]
+    pub format: Option<String>,
+    #[schemars(description = "Write the result to a sidecar file next to the input, e.g. talk.mp3 -> talk.srt (default: false)")]
+    pub write_sidecar: Option<bool>,
+    #[serde(flatten)]
+    pub transcription: TranscriptionParams,
 }
 
-#[derive(Debug, Deserialize, JsonSchema)]
+#[derive(Debug, Default, Deserialize, JsonSchema)]
 pub struct ListenRequest {
     #[schemars(description = "Maximum recording duration in milliseconds (default: 30000)")]
     pub timeout_ms: Option<u64>,
     #[schemars(description = "Silence duration in milliseconds before auto-stop (default: 2000)")]
     pub silence_timeout_ms: Option<u64>,
+    #[schemars(description = "With auto-stop, how long to wait for speech to begin in milliseconds (default: 5000)")]
+    pub onset_timeout_ms: Option<u64>,
+    #[schemars(description = "Milliseconds of audio kept before speech begins; earlier silence is not transcribed (default: 300)")]
+    pub pre_roll_ms: Option<u64>,
     #[schemars(description = "Auto-stop recording on silence detection (default: true)")]
     pub auto_stop: Option<bool>,
+    #[schemars(description = "Input device name, or part of it, as reported by list_input_devices (default: system default)")]
+    pub device: Option<String>,
+    #[schemars(description = "Voice activity detector used for auto-stop: adaptive (noise-floor tracking) or threshold (fixed level) (default: server setting)")]
+    pub vad: Option<String>,
+    #[schemars(description = "Transcribe while recording and send partial and final text as progress notifications (default: false)")]
+    pub stream: Option<bool>,
+    #[schemars(description = "How often the live audio is transcribed while streaming, in milliseconds (default: 1000)")]
+    pub stream_interval_ms: Option<u64>,
+    #[schemars(description = "Keep listening until stop_recording, splitting speech into utterances at pauses of silence_timeout_ms and sending each transcribed utterance as a progress notification; timeout_ms limits the length of one utterance (default: false)")]
+    pub continuous: Option<bool>,
+    #[serde(flatten)]
+    pub transcription: TranscriptionParams,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct StopRecordingRequest {
+    #[schemars(description = "Output format: json, text, srt, vtt or tsv (default: json)")]
+    pub format: Option<String>,
+    #[serde(flatten)]
+    pub transcription: TranscriptionParams,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct CalibrateRequest {
+    #[schemars(description = "How long to record room tone in milliseconds (default: 3000)")]
+    pub duration_ms: Option<u64>,
+    #[schemars(description = "Input device name, or part of it, as reported by list_input_devices (default: system default)")]
+    pub device: Option<String>,
+    #[schemars(description = "Save the recommended settings as the profile used by future sessions (default: true)")]
+    pub save: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct SubmitTranscriptionRequest {
+    #[schemars(description = "Path to the audio file to transcribe (WAV, MP3, FLAC, Ogg Vorbis or AAC/M4A)")]
+    pub file_path: String,
+    #[schemars(description = "Queue priority: low, normal or high; calls such as listen always run first (default: normal)")]
+    pub priority: Option<String>,
+    #[serde(flatten)]
+    pub transcription: TranscriptionParams,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct GetJobRequest {
+    #[schemars(description = "Job ID returned by submit_transcription")]
+    pub job_id: u64,
+    #[schemars(description = "Return only the finished transcript in this format: json, text, srt, vtt or tsv (default: the job status as JSON)")]
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub struct CancelJobRequest {
+    #[schemars(description = "Job ID returned by submit_transcription")]
+    pub job_id: u64,
 }
 
 #[derive(Debug, Clone)]
 pub struct VoiceToTextMcpServer {
     tool_router: ToolRouter<Self>,
-    service: Arc<Mutex<VoiceToTextService>>,
+    // The service is internally synchronised, so a long `listen` does not block the other tools
+    service: Arc<VoiceToTextService>,
+    jobs: JobQueue,
 }
 
 #[tool_router]
@@ -39,51 +171,255 @@ impl VoiceToTextMcpServer {
     pub fn new(service: VoiceToTextService) -> Self {
         Self {
             tool_router: Self::tool_router(),
-            service: Arc::new(Mutex::new(service)),
+            jobs: JobQueue::new(service.clone()),
+            service: Arc::new(service),
         }
     }
     
-    #[tool(description = "Transcribe an audio file to text using Whisper")]
+    #[tool(description = "Transcribe an audio file (WAV, MP3, FLAC, Ogg Vorbis, AAC/M4A) using Whisper. Returns JSON with the text, timed segments (optionally with per-word timings and confidence) and detected language, or SRT/WebVTT/TSV/plain text on request. When the request carries a progress token, the share of audio transcribed is sent as progress notifications")]
     pub async fn transcribe_file(
         &self,
-        Parameters(TranscribeFileRequest { file_path }): Parameters<TranscribeFileRequest>,
+        Parameters(request): Parameters<TranscribeFileRequest>,
+    ) -> String {
+        self.transcribe_file_with_progress(request, None).await
+    }
+
+    #[tool(description = "Record audio and return the transcription when complete, as JSON with the text, timed segments and detected language. With stream=true, partial and final text is sent as progress notifications while recording. With continuous=true, every utterance is sent as a progress notification until stop_recording, and the call returns them all as a JSON array. Otherwise, when the request carries a progress token, the elapsed time, input level and voice activity are sent while recording, followed by the transcription's progress")]
+    pub async fn listen(
+        &self,
+        Parameters(request): Parameters<ListenRequest>,
     ) -> String {
-        let service = self.service.lock().await;
-        match service.transcribe_wav_file(&file_path).await {
-            Ok(text) => text,
+        self.listen_with_progress(request, None).await
+    }
+
+    #[tool(description = "Start recording from the microphone and return immediately. Finish with stop_recording (to transcribe) or cancel_recording (to discard)")]
+    pub async fn start_recording(&self) -> String {
+        if !self.service.has_model() {
+            return format!("Error: {}", VoiceError::WhisperModelNotLoaded);
+        }
+
+        match self.service.start_listening().await {
+            Ok(_) => "Recording started".to_string(),
             Err(e) => format!("Error: {}", e),
         }
     }
 
-    #[tool(description = "Start recording audio and return transcribed text when complete")]
-    pub async fn listen(
+    #[tool(description = "Stop the recording started with start_recording and return its transcription (JSON by default), or end a continuous listen")]
+    pub async fn stop_recording(
         &self,
-        Parameters(ListenRequest { timeout_ms, silence_timeout_ms, auto_stop }): Parameters<ListenRequest>,
+        Parameters(StopRecordingRequest { format, transcription }): Parameters<StopRecordingRequest>,
+    ) -> String {
+        // The continuous listen call transcribes the last utterance and returns the results
+        if self.service.stop_continuous().is_ok() {
+            return "Continuous listening stopped".to_string();
+        }
+
+        let output_format = match format.as_deref().map(str::parse::<OutputFormat>) {
+            None => OutputFormat::Json,
+            Some(Ok(output_format)) => output_format,
+            Some(Err(e)) => return format!("Error: {}", e),
+        };
+
+        let options = transcription.to_options(self.service.get_transcription_options());
+        match self.service.stop_listening_with_options(&options).await {
+            Ok(transcription) => output_format.render(&transcription),
+            Err(e) => format!("Error: {}", e),
+        }
+    }
+
+    #[tool(description = "Stop the recording started with start_recording and discard the audio")]
+    pub async fn cancel_recording(&self) -> String {
+        match self.service.cancel_listening() {
+            Ok(()) => "Recording cancelled".to_string(),
+            Err(e) => format!("Error: {}", e),
+        }
+    }
+
+    #[tool(description = "List the audio input devices with their supported sample rates and channel counts, as JSON")]
+    pub async fn list_input_devices(&self) -> String {
+        match self.service.list_input_devices() {
+            Ok(devices) => serde_json::to_string_pretty(&devices).unwrap_or_else(|e| format!("Error: {}", e)),
+            Err(e) => format!("Error: {}", e),
+        }
+    }
+
+    #[tool(description = "Record a few seconds of room tone (stay quiet), measure the noise floor, peak level, DC offset and clipping, and switch listen's silence detection to the recommended settings. Returns the measurements as JSON")]
+    pub async fn calibrate(
+        &self,
+        Parameters(CalibrateRequest { duration_ms, device, save }): Parameters<CalibrateRequest>,
+    ) -> String {
+        let duration_ms = duration_ms.unwrap_or(DEFAULT_CALIBRATION_MS);
+        match self.service.calibrate(duration_ms, device.as_deref(), save.unwrap_or(true)).await {
+            Ok(report) => serde_json::to_string_pretty(&report).unwrap_or_else(|e| format!("Error: {}", e)),
+            Err(e) => format!("Error: {}", e),
+        }
+    }
+
+    #[tool(description = "Report whether a recording is in progress and how much audio it has captured, as JSON")]
+    pub async fn recording_status(&self) -> String {
+        serde_json::to_string_pretty(&self.service.recording_status())
+            .unwrap_or_else(|e| format!("Error: {}", e))
+    }
+
+    #[tool(description = "Queue an audio file for transcription in the background and return its job ID at once. Use for long files; follow it with get_job")]
+    pub async fn submit_transcription(
+        &self,
+        Parameters(SubmitTranscriptionRequest { file_path, priority, transcription }): Parameters<SubmitTranscriptionRequest>,
+    ) -> String {
+        let priority = match priority.as_deref().map(str::parse::<Priority>) {
+            None => Priority::Normal,
+            Some(Ok(priority)) => priority,
+            Some(Err(e)) => return format!("Error: {}", e),
+        };
+
+        let options = transcription.to_options(self.service.get_transcription_options());
+        match self.jobs.submit(&file_path, options, priority).and_then(|id| self.jobs.get(id)) {
+            Ok(status) => serde_json::to_string_pretty(&status.summary).unwrap_or_else(|e| format!("Error: {}", e)),
+            Err(e) => format!("Error: {}", e),
+        }
+    }
+
+    #[tool(description = "Report a transcription job's state (queued, running, completed, failed or cancelled), progress percentage and the segments transcribed so far, with the full result once completed, as JSON")]
+    pub async fn get_job(
+        &self,
+        Parameters(GetJobRequest { job_id, format }): Parameters<GetJobRequest>,
+    ) -> String {
+        let status = match self.jobs.get(job_id) {
+            Ok(status) => status,
+            Err(e) => return format!("Error: {}", e),
+        };
+
+        let Some(format) = format else {
+            return serde_json::to_string_pretty(&status).unwrap_or_else(|e| format!("Error: {}", e));
+        };
+        let output_format = match format.parse::<OutputFormat>() {
+            Ok(output_format) => output_format,
+            Err(e) => return format!("Error: {}", e),
+        };
+        match (&status.result, status.summary.state) {
+            (Some(transcription), _) => output_format.render(transcription),
+            (None, state) if !state.is_finished() => format!("Error: Job {} is still {}", job_id, state),
+            (None, _) => format!("Error: {}", status.summary.error.unwrap_or_default()),
+        }
+    }
+
+    #[tool(description = "List the transcription jobs with their state, priority and progress, as JSON")]
+    pub async fn list_jobs(&self) -> String {
+        serde_json::to_string_pretty(&self.jobs.list())
+            .unwrap_or_else(|e| format!("Error: {}", e))
+    }
+
+    #[tool(description = "Cancel a queued or running transcription job and return its status as JSON")]
+    pub async fn cancel_job(
+        &self,
+        Parameters(CancelJobRequest { job_id }): Parameters<CancelJobRequest>,
+    ) -> String {
+        match self.jobs.cancel(job_id) {
+            Ok(summary) => serde_json::to_string_pretty(&summary).unwrap_or_else(|e| format!("Error: {}", e)),
+            Err(e) => format!("Error: {}", e),
+        }
+    }
+}
+
+impl VoiceToTextMcpServer {
+    /// A server whose recordings and transcriptions stop when the client cancels the request
+    fn for_request(&self, context: &RequestContext<RoleServer>) -> Self {
+        Self {
+            service: Arc::new(self.service.with_cancellation(context.ct.clone())),
+            ..self.clone()
+        }
+    }
+
+    async fn transcribe_file_with_progress(
+        &self,
+        TranscribeFileRequest { file_path, format, write_sidecar, transcription }: TranscribeFileRequest,
+        progress: Option<ProgressReporter>,
+    ) -> String {
+        let output_format = match format.as_deref().map(str::parse::<OutputFormat>) {
+            None => OutputFormat::Json,
+            Some(Ok(output_format)) => output_format,
+            Some(Err(e)) => return format!("Error: {}", e),
+        };
+
+        let options = transcription.to_options(self.service.get_transcription_options());
+        let (events, forwarder) = forward_progress(progress);
+        let result = self.service.with_progress(events).transcribe_audio_file_with_options(&file_path, &options).await;
+        let _ = forwarder.await;
+        let transcription = match result {
+            Ok(transcription) => transcription,
+            Err(e) => return format!("Error: {}", e),
+        };
+
+        if write_sidecar.unwrap_or(false) {
+            match output_format.write_sidecar(&file_path, &transcription) {
+                Ok(path) => format!("Saved {} transcript to {}", output_format, path.display()),
+                Err(e) => format!("Error: Failed to write sidecar file: {}", e),
+            }
+        } else {
+            output_format.render(&transcription)
+        }
+    }
+
+    async fn listen_with_progress(
+        &self,
+        ListenRequest { timeout_ms, silence_timeout_ms, onset_timeout_ms, pre_roll_ms, auto_stop, device, vad, stream, stream_interval_ms, continuous, transcription }: ListenRequest,
+        progress: Option<ProgressReporter>,
     ) -> String {
         // Get parameters with defaults
-        let timeout = timeout_ms.unwrap_or(30000);
-        let silence_timeout = silence_timeout_ms.unwrap_or(2000);
-        let auto_stop_enabled = auto_stop.unwrap_or(true);
-
-        // Get debug status first
-        let debug_enabled = {
-            let service = self.service.lock().await;
-            service.get_debug_config().enabled
+        let defaults = ListenOptions::default();
+        let vad = match vad.as_deref().map(str::parse::<VadMode>) {
+            None => None,
+            Some(Ok(mode)) => Some(VadOptions { mode, ..self.service.get_vad_options() }),
+            Some(Err(e)) => return format!("Error: {}", e),
         };
+        let listen = ListenOptions {
+            timeout_ms: timeout_ms.unwrap_or(defaults.timeout_ms),
+            silence_timeout_ms: silence_timeout_ms.unwrap_or(defaults.silence_timeout_ms),
+            onset_timeout_ms: onset_timeout_ms.unwrap_or(defaults.onset_timeout_ms),
+            pre_roll_ms: pre_roll_ms.unwrap_or(defaults.pre_roll_ms),
+            auto_stop: auto_stop.unwrap_or(defaults.auto_stop),
+            device,
+            vad,
+        };
+
+        let debug_enabled = self.service.get_debug_config().enabled;
         
         if debug_enabled {
-            eprintln!("🎤 MCP: Starting voice recording with timeout: {}ms, silence_timeout: {}ms, auto_stop: {}", 
-                     timeout, silence_timeout, auto_stop_enabled);
+            eprintln!("🎤 MCP: Starting voice recording with timeout: {}ms, silence_timeout: {}ms, auto_stop: {}, device: {}", 
+                     listen.timeout_ms, listen.silence_timeout_ms, listen.auto_stop, listen.device.as_deref().unwrap_or("default"));
         }
 
         // Use the VoiceToTextService directly
-        let service = self.service.lock().await;
-        match service.start_listening_with_options(timeout, silence_timeout, auto_stop_enabled).await {
-            Ok(text) => {
+        let service = &self.service;
+        let options = transcription.to_options(service.get_transcription_options());
+        if continuous.unwrap_or(false) {
+            return self.listen_continuous(&listen, &options, progress).await;
+        }
+
+        let result = if stream.unwrap_or(false) {
+            let streaming = StreamingOptions {
+                interval_ms: stream_interval_ms.unwrap_or(StreamingOptions::default().interval_ms),
+                ..Default::default()
+            };
+            // Forward partial and final text while the recording is still running
+            let (events, forwarder) = forward_progress(progress);
+            let result = service.start_streaming_with_options(&listen, &options, &streaming, events).await;
+            let _ = forwarder.await;
+            result
+        } else {
+            // Report the recording's level and voice activity, then the transcription's progress
+            let (events, forwarder) = forward_progress(progress);
+            let result = service.with_progress(events).start_listening_with_options(&listen, &options).await;
+            let _ = forwarder.await;
+            result
+        };
+
+        match result {
+            Ok(transcription) => {
                 if debug_enabled {
                     eprintln!("🎤 MCP: Recording completed successfully");
                 }
-                text
+                transcription.to_json()
             }
             Err(e) => {
                 if debug_enabled {
@@ -93,6 +429,28 @@ impl VoiceToTextMcpServer {
             }
         }
     }
+
+    /// Report every utterance as it is transcribed and return them all once listening stops
+    async fn listen_continuous(&self, listen: &ListenOptions, options: &TranscriptionOptions, progress: Option<ProgressReporter>) -> String {
+        let (utterances, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let forwarder = tokio::spawn(async move {
+            let mut heard = Vec::new();
+            while let Some(utterance) = receiver.recv().await {
+                if let Some(ref progress) = progress {
+                    progress.report(serde_json::to_string(&utterance).unwrap_or_default(), None).await;
+                }
+                heard.push(utterance);
+            }
+            heard
+        });
+
+        let result = self.service.listen_continuous(listen, options, utterances).await;
+        let heard = forwarder.await.unwrap_or_default();
+        match result {
+            Ok(_) => serde_json::to_string_pretty(&heard).unwrap_or_else(|e| format!("Error: {}", e)),
+            Err(e) => format!("Error: {}", e),
+        }
+    }
 }
 
 impl ServerHandler for VoiceToTextMcpServer {
@@ -115,41 +473,58 @@ impl ServerHandler for VoiceToTextMcpServer {
     async fn call_tool(
         &self,
         request: CallToolRequestParam,
-        _context: RequestContext<RoleServer>,
+        context: RequestContext<RoleServer>,
     ) -> std::result::Result<CallToolResult, rmcp::Error> {
         // Use the router to call the appropriate tool method
         match request.name.as_ref() {
             "transcribe_file" => {
-                let file_path = request.arguments
-                    .as_ref()
-                    .and_then(|args| args.get("file_path"))
-                    .and_then(|v| v.as_str())
-                    .ok_or_else(|| rmcp::Error::invalid_request("file_path parameter required", None))?;
-                
-                let result = self.transcribe_file(
-                    Parameters(TranscribeFileRequest { file_path: file_path.to_string() })
-                ).await;
+                let progress = ProgressReporter::from_context(&context);
+                let result = self.for_request(&context).transcribe_file_with_progress(parse_arguments(&request)?, progress).await;
                 Ok(CallToolResult::success(vec![Content::text(result)]))
             },
             "listen" => {
-                let timeout_ms = request.arguments
-                    .as_ref()
-                    .and_then(|args| args.get("timeout_ms"))
-                    .and_then(|v| v.as_u64());
-                
-                let silence_timeout_ms = request.arguments
-                    .as_ref()
-                    .and_then(|args| args.get("silence_timeout_ms"))
-                    .and_then(|v| v.as_u64());
-                
-                let auto_stop = request.arguments
-                    .as_ref()
-                    .and_then(|args| args.get("auto_stop"))
-                    .and_then(|v| v.as_bool());
-                
-                let result = self.listen(
-                    Parameters(ListenRequest { timeout_ms, silence_timeout_ms, auto_stop })
-                ).await;
+                let progress = ProgressReporter::from_context(&context);
+                let result = self.for_request(&context).listen_with_progress(parse_arguments(&request)?, progress).await;
+                Ok(CallToolResult::success(vec![Content::text(result)]))
+            },
+            "start_recording" => {
+                let result = self.start_recording().await;
+                Ok(CallToolResult::success(vec![Content::text(result)]))
+            },
+            "stop_recording" => {
+                let result = self.for_request(&context).stop_recording(Parameters(parse_arguments(&request)?)).await;
+                Ok(CallToolResult::success(vec![Content::text(result)]))
+            },
+            "cancel_recording" => {
+                let result = self.cancel_recording().await;
+                Ok(CallToolResult::success(vec![Content::text(result)]))
+            },
+            "list_input_devices" => {
+                let result = self.list_input_devices().await;
+                Ok(CallToolResult::success(vec![Content::text(result)]))
+            },
+            "calibrate" => {
+                let result = self.for_request(&context).calibrate(Parameters(parse_arguments(&request)?)).await;
+                Ok(CallToolResult::success(vec![Content::text(result)]))
+            },
+            "recording_status" => {
+                let result = self.recording_status().await;
+                Ok(CallToolResult::success(vec![Content::text(result)]))
+            },
+            "submit_transcription" => {
+                let result = self.submit_transcription(Parameters(parse_arguments(&request)?)).await;
+                Ok(CallToolResult::success(vec![Content::text(result)]))
+            },
+            "get_job" => {
+                let result = self.get_job(Parameters(parse_arguments(&request)?)).await;
+                Ok(CallToolResult::success(vec![Content::text(result)]))
+            },
+            "list_jobs" => {
+                let result = self.list_jobs().await;
+                Ok(CallToolResult::success(vec![Content::text(result)]))
+            },
+            "cancel_job" => {
+                let result = self.cancel_job(Parameters(parse_arguments(&request)?)).await;
                 Ok(CallToolResult::success(vec![Content::text(result)]))
             },
             _ => Err(rmcp::Error::method_not_found::<rmcp::model::CallToolRequestMethod>()),
@@ -157,6 +532,87 @@ impl ServerHandler for VoiceToTextMcpServer {
     }
 }
 
+/// Sends progress notifications for a tool call whose client supplied a progress token
+struct ProgressReporter {
+    peer: Peer<RoleServer>,
+    token: ProgressToken,
+    progress: AtomicU32,
+    /// One more than the last percentage sent, so that 0% is still sent once
+    percent: AtomicU32,
+}
+
+impl ProgressReporter {
+    fn from_context(context: &RequestContext<RoleServer>) -> Option<Self> {
+        context.meta.get_progress_token().map(|token| Self {
+            peer: context.peer.clone(),
+            token,
+            progress: AtomicU32::new(0),
+            percent: AtomicU32::new(0),
+        })
+    }
+
+    /// Send `message`, as `percent` out of 100 when the work has a known total
+    async fn report(&self, message: String, percent: Option<f32>) {
+        let (progress, total) = match percent {
+            Some(percent) => {
+                // Progress must increase with every notification, so skip repeats of the same percentage
+                let percent = percent.clamp(0.0, 100.0) as u32;
+                if self.percent.fetch_max(percent + 1, Ordering::Relaxed) > percent {
+                    return;
+                }
+                (percent, Some(100))
+            }
+            // Recording has no known total, so count the notifications instead
+            None => (self.progress.fetch_add(1, Ordering::Relaxed) + 1, None),
+        };
+        let _ = self.peer.notify_progress(ProgressNotificationParam {
+            progress_token: self.token.clone(),
+            progress,
+            total,
+            message: Some(message),
+        }).await;
+    }
+}
+
+/// An event that is sent to the client as a progress notification
+trait ProgressUpdate: Serialize {
+    /// How much of the work is done, 0-100, when its total is known
+    fn percent(&self) -> Option<f32> {
+        None
+    }
+}
+
+impl ProgressUpdate for ProgressEvent {
+    fn percent(&self) -> Option<f32> {
+        match self {
+            ProgressEvent::Transcribing { percent } => Some(*percent),
+            ProgressEvent::Recording { .. } => None,
+        }
+    }
+}
+
+impl ProgressUpdate for StreamEvent {}
+
+/// Send every event from the returned channel as a progress notification, until the sender is dropped
+fn forward_progress<T: ProgressUpdate + Send + 'static>(progress: Option<ProgressReporter>) -> (UnboundedSender<T>, JoinHandle<()>) {
+    let (events, mut receiver) = tokio::sync::mpsc::unbounded_channel::<T>();
+    let forwarder = tokio::spawn(async move {
+        while let Some(event) = receiver.recv().await {
+            if let Some(ref progress) = progress {
+                progress.report(serde_json::to_string(&event).unwrap_or_default(), event.percent()).await;
+            }
+        }
+    });
+    (events, forwarder)
+}
+
+/// Deserialize tool call arguments into the tool's request type
+fn parse_arguments<T: DeserializeOwned>(request: &CallToolRequestParam) -> std::result::Result<T, rmcp::Error> {
+    let arguments = request.arguments.clone().unwrap_or_default();
+    serde_json::from_value(serde_json::Value::Object(arguments))
+        .map_err(|e| rmcp::Error::invalid_params(format!("Invalid arguments for {}: {}", request.name, e), None))
+}
+
 pub async fn run_mcp_server(service: VoiceToTextService) -> anyhow::Result<()> {
     // Import the platform compatibility layer to enable Send/Sync on macOS
     #[allow(unused_imports)]
@@ -0,0 +1,128 @@
+//! Ogg Opus decoding through libopus, registered with symphonia alongside its own decoders
+
+use audiopus::coder::{Decoder as LibopusDecoder, GenericCtl};
+use audiopus::packet::Packet as OpusPacket;
+use audiopus::{Channels as OpusChannels, MutSignals, SampleRate};
+use symphonia::core::audio::{AsAudioBufferRef, AudioBuffer, AudioBufferRef, Signal, SignalSpec};
+use symphonia::core::codecs::{
+    CodecDescriptor, CodecParameters, Decoder, DecoderOptions, FinalizeResult, CODEC_TYPE_OPUS,
+};
+use symphonia::core::errors::{decode_error, unsupported_error, Result};
+use symphonia::core::formats::Packet;
+use std::sync::{Mutex, PoisonError};
+
+/// Opus always decodes at 48kHz in Ogg files
+const OPUS_SAMPLE_RATE: u32 = 48000;
+
+/// Frames in the longest Opus packet (120ms)
+const MAX_PACKET_FRAMES: usize = 5760;
+
+/// Decoder for the mono and stereo Opus streams symphonia's Ogg reader demuxes
+pub(crate) struct OpusDecoder {
+    params: CodecParameters,
+    /// Symphonia decoders must be `Sync`; libopus' is only `Send`, and is only used through `&mut self`
+    decoder: Mutex<LibopusDecoder>,
+    channels: usize,
+    /// Frames of encoder delay still to drop from the start of the stream (the header's pre-skip)
+    pre_skip: usize,
+    interleaved: Vec<f32>,
+    buf: AudioBuffer<f32>,
+}
+
+impl OpusDecoder {
+    /// Pre-skip field of the OpusHead identification header
+    fn pre_skip(params: &CodecParameters) -> usize {
+        match params.extra_data.as_deref() {
+            Some(header) if header.len() >= 12 && header.starts_with(b"OpusHead") => {
+                u16::from_le_bytes([header[10], header[11]]) as usize
+            }
+            _ => params.delay.unwrap_or(0) as usize,
+        }
+    }
+}
+
+impl Decoder for OpusDecoder {
+    fn try_new(params: &CodecParameters, _options: &DecoderOptions) -> Result<Self> {
+        let channels = match params.channels {
+            Some(channels) => channels,
+            None => return unsupported_error("opus: channel layout is missing"),
+        };
+        let opus_channels = match channels.count() {
+            1 => OpusChannels::Mono,
+            2 => OpusChannels::Stereo,
+            _ => return unsupported_error("opus: only mono and stereo streams are supported"),
+        };
+        let decoder = match LibopusDecoder::new(SampleRate::Hz48000, opus_channels) {
+            Ok(decoder) => decoder,
+            Err(_) => return decode_error("opus: failed to create decoder"),
+        };
+
+        let spec = SignalSpec::new(OPUS_SAMPLE_RATE, channels);
+        Ok(Self {
+            params: params.clone(),
+            decoder: Mutex::new(decoder),
+            channels: channels.count(),
+            pre_skip: Self::pre_skip(params),
+            interleaved: vec![0.0; MAX_PACKET_FRAMES * channels.count()],
+            buf: AudioBuffer::new(MAX_PACKET_FRAMES as u64, spec),
+        })
+    }
+
+    fn supported_codecs() -> &'static [CodecDescriptor] {
+        &[CodecDescriptor {
+            codec: CODEC_TYPE_OPUS,
+            short_name: "opus",
+            long_name: "Opus (libopus)",
+            inst_func: |params, options| Ok(Box::new(OpusDecoder::try_new(params, options)?)),
+        }]
+    }
+
+    fn reset(&mut self) {
+        // Only fails for an invalid decoder, which `try_new` never returns
+        let decoder = self.decoder.get_mut().unwrap_or_else(PoisonError::into_inner);
+        let _ = decoder.reset_state();
+    }
+
+    fn codec_params(&self) -> &CodecParameters {
+        &self.params
+    }
+
+    fn decode(&mut self, packet: &Packet) -> Result<AudioBufferRef<'_>> {
+        self.buf.clear();
+
+        let input = match OpusPacket::try_from(packet.buf()) {
+            Ok(input) => input,
+            Err(_) => return decode_error("opus: empty packet"),
+        };
+        let output = match MutSignals::try_from(&mut self.interleaved[..]) {
+            Ok(output) => output,
+            Err(_) => return decode_error("opus: output buffer too large"),
+        };
+        let decoder = self.decoder.get_mut().unwrap_or_else(PoisonError::into_inner);
+        let frames = match decoder.decode_float(Some(input), output, false) {
+            Ok(frames) => frames,
+            Err(_) => return decode_error("opus: invalid packet"),
+        };
+
+        // libopus hands back interleaved frames; symphonia buffers are planar
+        self.buf.render_reserved(Some(frames));
+        for (channel, plane) in self.buf.planes_mut().planes().iter_mut().enumerate() {
+            for (frame, sample) in plane.iter_mut().enumerate() {
+                *sample = self.interleaved[frame * self.channels + channel];
+            }
+        }
+
+        let skip = self.pre_skip.min(frames);
+        self.pre_skip -= skip;
+        self.buf.trim(packet.trim_start() as usize + skip, packet.trim_end() as usize);
+        Ok(self.buf.as_audio_buffer_ref())
+    }
+
+    fn finalize(&mut self) -> FinalizeResult {
+        FinalizeResult::default()
+    }
+
+    fn last_decoded(&self) -> AudioBufferRef<'_> {
+        self.buf.as_audio_buffer_ref()
+    }
+}
@@ -0,0 +1,136 @@
+use crate::error::{Result, VoiceError};
+use crate::transcription::Transcription;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Output formats a transcription can be rendered to
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Plain transcript text
+    #[default]
+    Text,
+    Json,
+    Srt,
+    Vtt,
+    Tsv,
+}
+
+impl OutputFormat {
+    /// File extension used for sidecar files
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Text => "txt",
+            OutputFormat::Json => "json",
+            OutputFormat::Srt => "srt",
+            OutputFormat::Vtt => "vtt",
+            OutputFormat::Tsv => "tsv",
+        }
+    }
+
+    pub fn render(&self, transcription: &Transcription) -> String {
+        match self {
+            OutputFormat::Text => transcription.text.clone(),
+            OutputFormat::Json => transcription.to_json(),
+            OutputFormat::Srt => render_srt(transcription),
+            OutputFormat::Vtt => render_vtt(transcription),
+            OutputFormat::Tsv => render_tsv(transcription),
+        }
+    }
+
+    /// Render the transcription and write it next to `input_path` with this format's extension
+    pub fn write_sidecar(&self, input_path: &str, transcription: &Transcription) -> Result<PathBuf> {
+        let sidecar_path = Path::new(input_path).with_extension(self.extension());
+        if sidecar_path == Path::new(input_path) {
+            return Err(VoiceError::Io(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("Refusing to overwrite the input file {} with its own transcript", input_path),
+            )));
+        }
+
+        std::fs::write(&sidecar_path, self.render(transcription))?;
+        Ok(sidecar_path)
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" | "txt" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "srt" => Ok(OutputFormat::Srt),
+            "vtt" | "webvtt" => Ok(OutputFormat::Vtt),
+            "tsv" => Ok(OutputFormat::Tsv),
+            other => Err(format!("Unknown output format '{}' (expected text, json, srt, vtt or tsv)", other)),
+        }
+    }
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            OutputFormat::Text => "text",
+            OutputFormat::Json => "json",
+            OutputFormat::Srt => "srt",
+            OutputFormat::Vtt => "vtt",
+            OutputFormat::Tsv => "tsv",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Format seconds as `HH:MM:SS<separator>mmm`
+fn format_timestamp(seconds: f32, separator: char) -> String {
+    let total_ms = (seconds.max(0.0) * 1000.0).round() as u64;
+    let hours = total_ms / 3_600_000;
+    let minutes = (total_ms / 60_000) % 60;
+    let secs = (total_ms / 1000) % 60;
+    let millis = total_ms % 1000;
+    format!("{:02}:{:02}:{:02}{}{:03}", hours, minutes, secs, separator, millis)
+}
+
+fn render_srt(transcription: &Transcription) -> String {
+    let mut output = String::new();
+    for (index, segment) in transcription.segments.iter().enumerate() {
+        let _ = write!(
+            output,
+            "{}\n{} --> {}\n{}\n\n",
+            index + 1,
+            format_timestamp(segment.start, ','),
+            format_timestamp(segment.end, ','),
+            segment.text.trim()
+        );
+    }
+    output
+}
+
+fn render_vtt(transcription: &Transcription) -> String {
+    let mut output = String::from("WEBVTT\n\n");
+    for segment in &transcription.segments {
+        let _ = write!(
+            output,
+            "{} --> {}\n{}\n\n",
+            format_timestamp(segment.start, '.'),
+            format_timestamp(segment.end, '.'),
+            segment.text.trim()
+        );
+    }
+    output
+}
+
+/// Tab-separated `start`/`end` in milliseconds and text, matching whisper.cpp's `--output-tsv`
+fn render_tsv(transcription: &Transcription) -> String {
+    let mut output = String::from("start\tend\ttext\n");
+    for segment in &transcription.segments {
+        let _ = writeln!(
+            output,
+            "{}\t{}\t{}",
+            (segment.start * 1000.0).round() as u64,
+            (segment.end * 1000.0).round() as u64,
+            segment.text.trim().replace(['\t', '\n'], " ")
+        );
+    }
+    output
+}
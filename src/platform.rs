@@ -1,4 +1,6 @@
 use crate::error::{Result, VoiceError};
+use gag::Gag;
+use std::sync::Mutex;
 use whisper_rs::{WhisperContext, WhisperContextParameters};
 
 // Conditional logging macros
@@ -62,11 +64,37 @@ pub fn run_whisper_transcription(
     }
 }
 
+/// Number of quiet operations running and the stderr redirect they share
+static QUIET_STDERR: Mutex<(usize, Option<Gag>)> = Mutex::new((0, None));
+
+/// Keeps stderr redirected while alive. `gag` allows only one redirect at a time, so concurrent
+/// transcriptions share one that ends when the last of them finishes.
+struct QuietStderr;
+
+impl QuietStderr {
+    fn new() -> Result<Self> {
+        let mut quiet = QUIET_STDERR.lock().unwrap();
+        if quiet.0 == 0 {
+            quiet.1 = Some(Gag::stderr().map_err(|e| VoiceError::Platform(format!("Failed to redirect stderr: {}", e)))?);
+        }
+        quiet.0 += 1;
+        Ok(Self)
+    }
+}
+
+impl Drop for QuietStderr {
+    fn drop(&mut self) {
+        let mut quiet = QUIET_STDERR.lock().unwrap();
+        quiet.0 -= 1;
+        if quiet.0 == 0 {
+            quiet.1 = None;
+        }
+    }
+}
+
 fn load_whisper_quietly(model_path: &str) -> Result<WhisperContext> {
-    use gag::Gag;
-    
     // Suppress stderr during model loading
-    let _stderr_gag = Gag::stderr().map_err(|e| VoiceError::Platform(format!("Failed to redirect stderr: {}", e)))?;
+    let _stderr_gag = QuietStderr::new()?;
     
     let ctx = WhisperContext::new_with_params(
         model_path,
@@ -78,10 +106,8 @@ fn load_whisper_quietly(model_path: &str) -> Result<WhisperContext> {
 }
 
 fn create_state_quietly(ctx: &WhisperContext) -> Result<whisper_rs::WhisperState> {
-    use gag::Gag;
-    
     // Suppress stderr during state creation
-    let _stderr_gag = Gag::stderr().map_err(|e| VoiceError::Platform(format!("Failed to redirect stderr: {}", e)))?;
+    let _stderr_gag = QuietStderr::new()?;
     
     let state = ctx.create_state().map_err(|e| VoiceError::WhisperTranscription(e.to_string()))?;
     
@@ -94,10 +120,8 @@ fn run_transcription_quietly(
     params: whisper_rs::FullParams, 
     audio_data: &[f32]
 ) -> Result<()> {
-    use gag::Gag;
-    
     // Suppress stderr during transcription
-    let _stderr_gag = Gag::stderr().map_err(|e| VoiceError::Platform(format!("Failed to redirect stderr: {}", e)))?;
+    let _stderr_gag = QuietStderr::new()?;
     
     state.full(params, audio_data)
         .map(|_| ())
@@ -107,26 +131,28 @@ fn run_transcription_quietly(
     Ok(())
 }
 
+/// Hardware acceleration backends compiled into whisper.cpp, from this crate's cargo features
+pub fn acceleration_features() -> Vec<&'static str> {
+    [
+        ("CUDA", cfg!(feature = "cuda")),
+        ("Metal", cfg!(feature = "metal")),
+        ("CoreML", cfg!(feature = "coreml")),
+        ("Vulkan", cfg!(feature = "vulkan")),
+        ("OpenBLAS", cfg!(feature = "openblas")),
+    ]
+    .into_iter()
+    .filter(|&(_, enabled)| enabled)
+    .map(|(name, _)| name)
+    .collect()
+}
+
 pub fn log_acceleration_status(debug_enabled: bool) {
-    debug_println!(debug_enabled, "Initializing Whisper with hardware acceleration:");
-    
-    #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
-    debug_println!(debug_enabled, "  Platform: macOS Apple Silicon (Metal + CoreML enabled)");
-    
-    #[cfg(all(target_os = "macos", target_arch = "x86_64"))]
-    debug_println!(debug_enabled, "  Platform: macOS Intel (Metal enabled)");
-    
-    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
-    debug_println!(debug_enabled, "  Platform: Linux x86_64 (CUDA enabled)");
-    
-    #[cfg(all(target_os = "windows", target_arch = "x86_64"))]
-    debug_println!(debug_enabled, "  Platform: Windows x86_64 (CUDA enabled)");
-    
-    #[cfg(not(any(
-        all(target_os = "macos", target_arch = "aarch64"),
-        all(target_os = "macos", target_arch = "x86_64"),
-        all(target_os = "linux", target_arch = "x86_64"),
-        all(target_os = "windows", target_arch = "x86_64")
-    )))]
-    debug_println!(debug_enabled, "  Platform: No hardware acceleration (CPU-only mode)");
-}
\ No newline at end of file
+    debug_println!(debug_enabled, "Initializing Whisper on {} {}:", std::env::consts::OS, std::env::consts::ARCH);
+
+    let features = acceleration_features();
+    if features.is_empty() {
+        debug_println!(debug_enabled, "  Acceleration: none (CPU-only build; enable the cuda, metal, coreml, vulkan or openblas feature)");
+    } else {
+        debug_println!(debug_enabled, "  Acceleration: {} enabled", features.join(" + "));
+    }
+}
@@ -24,6 +24,10 @@ impl_send_sync_on_macos!(VoiceToTextService);
 // Also apply to the AudioCapture type  
 impl_send_sync_on_macos!(crate::audio::AudioCapture);
 
+// CpalSource owns the cpal stream, and AudioSource implementations must be Send + Sync
+#[cfg(feature = "microphone")]
+impl_send_sync_on_macos!(crate::audio_source::CpalSource);
+
 // Note: We cannot implement Send/Sync for external types like cpal::Stream
 // due to Rust's orphan rules. The unsafe implementations above for our own types
 // should be sufficient for the MCP server to work on macOS.
@@ -0,0 +1,17 @@
+use crate::vad::VoiceActivity;
+use serde::Serialize;
+
+/// How far a recording or transcription has got, for callers that show it while they wait
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ProgressEvent {
+    /// Sent about every `RECORDING_PROGRESS_INTERVAL_MS` while recording
+    Recording {
+        elapsed_seconds: f32,
+        /// Input level of the latest audio in dBFS
+        level_db: f32,
+        voice: VoiceActivity,
+    },
+    /// Share of the audio transcribed so far, 0-100
+    Transcribing { percent: f32 },
+}
@@ -0,0 +1,191 @@
+use crate::audio::AudioBuffer;
+use crate::error::Result;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::sync::Mutex;
+
+const BYTES_PER_SAMPLE: usize = std::mem::size_of::<f32>();
+
+/// Where the spilled part of a recording lives: a temporary file, or a stand-in in tests
+pub(crate) trait SpillStorage: Read + Write + Seek + Send {}
+
+impl<T: Read + Write + Seek + Send> SpillStorage for T {}
+
+/// Captured audio that keeps at most `memory_limit` samples in RAM.
+///
+/// Once the in-memory part reaches the limit it is appended to an anonymous temporary file
+/// (deleted automatically when the recording is dropped), so a long capture costs disk space
+/// instead of memory. Reads cover both parts transparently.
+pub struct RecordedAudio {
+    /// Most recent samples, after everything on disk
+    memory: Vec<f32>,
+    spill: Option<Mutex<Box<dyn SpillStorage>>>,
+    spilled_samples: usize,
+    /// Samples dropped from the front by `trim_start`
+    trimmed: usize,
+    /// Samples freed by `discard_before`; the first sample kept on disk or in memory has this index
+    discarded: usize,
+    memory_limit: usize,
+    sample_rate: u32,
+    write_buffer: Vec<u8>,
+}
+
+impl RecordedAudio {
+    /// An empty recording that spills to disk beyond `memory_limit_bytes`
+    pub fn new(sample_rate: u32, memory_limit_bytes: usize) -> Self {
+        Self {
+            memory: Vec::new(),
+            spill: None,
+            spilled_samples: 0,
+            trimmed: 0,
+            discarded: 0,
+            memory_limit: (memory_limit_bytes / BYTES_PER_SAMPLE).max(1),
+            sample_rate,
+            write_buffer: Vec::new(),
+        }
+    }
+
+    /// Spill to `storage` instead of a temporary file
+    #[cfg(test)]
+    pub(crate) fn with_spill_storage(mut self, storage: impl SpillStorage + 'static) -> Self {
+        self.spill = Some(Mutex::new(Box::new(storage)));
+        self
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub(crate) fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate;
+    }
+
+    /// Total number of samples, in memory and on disk
+    pub fn len(&self) -> usize {
+        self.end() - self.trimmed
+    }
+
+    /// Index just past the last sample, counting trimmed ones
+    fn end(&self) -> usize {
+        self.discarded + self.spilled_samples + self.memory.len()
+    }
+
+    /// Drop the first `samples` samples, e.g. silence before someone started speaking
+    pub fn trim_start(&mut self, samples: usize) {
+        self.trimmed += samples.min(self.len());
+    }
+
+    /// Free the samples before index `index` once nothing will read them again, e.g. audio
+    /// already transcribed while a capture keeps running.
+    ///
+    /// Later samples keep their indices, and reads that reach back past `index` start at the
+    /// oldest sample still kept. Audio on disk is only freed once all of it can go.
+    pub fn discard_before(&mut self, index: usize) {
+        let target = (self.trimmed + index).min(self.end());
+        if target <= self.discarded {
+            return;
+        }
+
+        let mut amount = target - self.discarded;
+        if self.spilled_samples > 0 {
+            if amount < self.spilled_samples {
+                return;
+            }
+            self.spill = None;
+            self.discarded += self.spilled_samples;
+            amount -= self.spilled_samples;
+            self.spilled_samples = 0;
+        }
+
+        let amount = amount.min(self.memory.len());
+        self.memory.drain(..amount);
+        self.discarded += amount;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn duration_seconds(&self) -> f32 {
+        if self.sample_rate == 0 {
+            return 0.0;
+        }
+        self.len() as f32 / self.sample_rate as f32
+    }
+
+    /// Whether part of the recording lives in the temporary file
+    pub fn is_spilled(&self) -> bool {
+        self.spilled_samples > 0
+    }
+
+    /// Append samples, moving the in-memory part to disk when it reaches the limit
+    pub fn append(&mut self, samples: &[f32]) -> Result<()> {
+        self.memory.extend_from_slice(samples);
+        if self.memory.len() >= self.memory_limit {
+            self.spill_memory()?;
+        }
+        Ok(())
+    }
+
+    fn spill_memory(&mut self) -> Result<()> {
+        if self.spill.is_none() {
+            self.spill = Some(Mutex::new(Box::new(tempfile::tempfile()?)));
+        }
+
+        self.write_buffer.clear();
+        self.write_buffer.extend(self.memory.iter().flat_map(|s| s.to_le_bytes()));
+
+        if let Some(ref spill) = self.spill {
+            // Write right after the samples already spilled rather than at the end of the file,
+            // so a write that failed part-way is overwritten by the next attempt
+            let mut file = spill.lock().unwrap();
+            file.seek(SeekFrom::Start((self.spilled_samples * BYTES_PER_SAMPLE) as u64))?;
+            file.write_all(&self.write_buffer)?;
+        }
+
+        self.spilled_samples += self.memory.len();
+        self.memory.clear();
+        // Keep the memory ceiling real: a long recording must not hold on to old capacity
+        self.memory.shrink_to(self.memory_limit);
+        self.write_buffer.clear();
+        self.write_buffer.shrink_to_fit();
+        Ok(())
+    }
+
+    /// Read up to `count` samples starting at sample index `start`
+    pub fn read(&self, start: usize, count: usize) -> Result<AudioBuffer> {
+        let start = self.trimmed + start.min(self.len());
+        let end = start.saturating_add(count).min(self.end());
+        // Positions within what is still stored: disk first, then memory
+        let start = start.max(self.discarded) - self.discarded;
+        let end = end.max(self.discarded) - self.discarded;
+        let mut samples = Vec::with_capacity(end.saturating_sub(start));
+
+        if start < self.spilled_samples {
+            let disk_end = end.min(self.spilled_samples);
+            let mut bytes = vec![0u8; (disk_end - start) * BYTES_PER_SAMPLE];
+            if let Some(ref spill) = self.spill {
+                let mut file = spill.lock().unwrap();
+                file.seek(SeekFrom::Start((start * BYTES_PER_SAMPLE) as u64))?;
+                file.read_exact(&mut bytes)?;
+            }
+            samples.extend(bytes.chunks_exact(BYTES_PER_SAMPLE).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])));
+        }
+
+        if end > self.spilled_samples {
+            let memory_start = start.saturating_sub(self.spilled_samples);
+            samples.extend_from_slice(&self.memory[memory_start..end - self.spilled_samples]);
+        }
+
+        Ok(AudioBuffer::new(samples, self.sample_rate))
+    }
+
+    /// Everything from sample index `start` to the end
+    pub fn read_from(&self, start: usize) -> Result<AudioBuffer> {
+        self.read(start, self.len().saturating_sub(start))
+    }
+
+    /// Load the whole recording into memory
+    pub fn to_buffer(&self) -> Result<AudioBuffer> {
+        self.read_from(0)
+    }
+}
@@ -0,0 +1,268 @@
+use crate::error::{Result, VoiceError};
+use std::f64::consts::PI;
+use std::str::FromStr;
+
+/// Most filter phases kept for one conversion; rate pairs whose reduced ratio needs more
+/// use the nearest of these phases
+const MAX_PHASES: usize = 1024;
+
+/// Trade-off between resampling speed and how well frequencies above the new Nyquist
+/// frequency are kept from aliasing into the speech band
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ResampleQuality {
+    /// Short filter, roughly 60dB of stopband attenuation
+    Fast,
+    /// Roughly 85dB of stopband attenuation
+    #[default]
+    Balanced,
+    /// Long filter with a sharp transition band, roughly 120dB of stopband attenuation
+    Best,
+}
+
+impl ResampleQuality {
+    /// Sinc zero crossings on each side of the centre, passband edge as a fraction of the
+    /// lower Nyquist frequency, and the Kaiser window's beta
+    fn filter_params(&self) -> (usize, f64, f64) {
+        match self {
+            ResampleQuality::Fast => (8, 0.85, 6.0),
+            ResampleQuality::Balanced => (16, 0.9, 8.6),
+            ResampleQuality::Best => (32, 0.95, 12.0),
+        }
+    }
+}
+
+impl FromStr for ResampleQuality {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "fast" => Ok(ResampleQuality::Fast),
+            "balanced" => Ok(ResampleQuality::Balanced),
+            "best" => Ok(ResampleQuality::Best),
+            other => Err(format!("Unknown resample quality '{}' (expected fast, balanced or best)", other)),
+        }
+    }
+}
+
+impl std::fmt::Display for ResampleQuality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ResampleQuality::Fast => "fast",
+            ResampleQuality::Balanced => "balanced",
+            ResampleQuality::Best => "best",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Band-limited polyphase resampler with a Kaiser-windowed sinc filter.
+///
+/// Input can be fed in chunks of any size, e.g. straight from a capture callback; the output is
+/// the same as resampling all of it at once. Each output sample needs a few input samples after
+/// it, so the last ones only come out of `flush` once the input is complete.
+///
+/// ```
+/// use voice_to_text_mcp::resample::{ResampleQuality, Resampler};
+///
+/// let mut resampler = Resampler::new(48000, 16000, ResampleQuality::Balanced).unwrap();
+/// let mut output = Vec::new();
+/// for chunk in vec![0.0f32; 4800].chunks(480) {
+///     resampler.process(chunk, &mut output);
+/// }
+/// resampler.flush(&mut output);
+/// assert_eq!(output.len(), 1600);
+/// ```
+pub struct Resampler {
+    /// Reduced conversion ratio: `up` output samples for every `down` input samples
+    up: u64,
+    down: u64,
+    /// Input samples used on each side of an output sample
+    half: usize,
+    phases: usize,
+    /// `phases` filters of `2 * half` taps each
+    kernel: Vec<f32>,
+    /// Input samples still needed, the first of which has index `history_start`
+    history: Vec<f32>,
+    history_start: u64,
+    received: u64,
+    produced: u64,
+}
+
+impl Resampler {
+    pub fn new(input_rate: u32, output_rate: u32, quality: ResampleQuality) -> Result<Self> {
+        if input_rate == 0 || output_rate == 0 {
+            return Err(VoiceError::AudioProcessing(format!(
+                "Cannot resample from {}Hz to {}Hz", input_rate, output_rate
+            )));
+        }
+
+        let divisor = gcd(input_rate as u64, output_rate as u64);
+        let up = output_rate as u64 / divisor;
+        let down = input_rate as u64 / divisor;
+
+        let (half, phases, kernel) = if up == down {
+            (0, 1, Vec::new())
+        } else {
+            let (zero_crossings, rolloff, beta) = quality.filter_params();
+            // Cut off below whichever Nyquist frequency is lower, in units of the input rate
+            let cutoff = rolloff * (up as f64 / down as f64).min(1.0);
+            let half = (zero_crossings as f64 / cutoff).ceil() as usize;
+            let phases = (up as usize).min(MAX_PHASES);
+            (half, phases, build_kernel(half, phases, cutoff, beta))
+        };
+
+        Ok(Self {
+            up,
+            down,
+            half,
+            phases,
+            kernel,
+            history: Vec::new(),
+            history_start: 0,
+            received: 0,
+            produced: 0,
+        })
+    }
+
+    /// Resample the next chunk of input, appending every output sample that is complete
+    pub fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        if self.up == self.down {
+            output.extend_from_slice(input);
+            self.received += input.len() as u64;
+            self.produced = self.received;
+            return;
+        }
+
+        self.history.extend_from_slice(input);
+        self.received += input.len() as u64;
+        self.emit(output, false);
+    }
+
+    /// Append the remaining output, treating the input as ended, and reset for a new stream
+    pub fn flush(&mut self, output: &mut Vec<f32>) {
+        if self.up != self.down {
+            self.emit(output, true);
+        }
+        self.history.clear();
+        self.history_start = 0;
+        self.received = 0;
+        self.produced = 0;
+    }
+
+    fn emit(&mut self, output: &mut Vec<f32>, input_ended: bool) {
+        let taps = 2 * self.half;
+        let lookahead = self.half as u64;
+        // The whole input maps to ceil(received * up / down) output samples
+        let total = (self.received * self.up).div_ceil(self.down);
+
+        while self.produced < total {
+            let position = self.produced * self.down;
+            let base = position / self.up;
+            if !input_ended && base + lookahead >= self.received {
+                break;
+            }
+
+            let phase = ((position % self.up) as usize * self.phases) / self.up as usize;
+            let filter = &self.kernel[phase * taps..(phase + 1) * taps];
+            let first = base as i64 + 1 - self.half as i64;
+            output.push(self.convolve(filter, first));
+            self.produced += 1;
+        }
+
+        // Drop input no later output sample reaches back to
+        let next_base = self.produced * self.down / self.up;
+        let keep_from = (next_base + 1).saturating_sub(self.half as u64).min(self.received);
+        if keep_from > self.history_start {
+            self.history.drain(..(keep_from - self.history_start) as usize);
+            self.history_start = keep_from;
+        }
+    }
+
+    /// Apply `filter` to the input starting at index `first`; samples before the start or past
+    /// the end of the input count as silence
+    fn convolve(&self, filter: &[f32], first: i64) -> f32 {
+        let last = first + filter.len() as i64;
+        if first >= self.history_start as i64 && last <= self.received as i64 {
+            let start = (first - self.history_start as i64) as usize;
+            return self.history[start..start + filter.len()].iter().zip(filter).map(|(x, h)| x * h).sum();
+        }
+
+        filter
+            .iter()
+            .enumerate()
+            .map(|(k, h)| {
+                let index = first + k as i64;
+                if index < self.history_start as i64 || index >= self.received as i64 {
+                    0.0
+                } else {
+                    self.history[(index - self.history_start as i64) as usize] * h
+                }
+            })
+            .sum()
+    }
+}
+
+/// Resample a whole buffer at once
+pub fn resample(samples: &[f32], input_rate: u32, output_rate: u32, quality: ResampleQuality) -> Result<Vec<f32>> {
+    let mut resampler = Resampler::new(input_rate, output_rate, quality)?;
+    let expected = (samples.len() as u64 * resampler.up).div_ceil(resampler.down) as usize;
+    let mut output = Vec::with_capacity(expected);
+    resampler.process(samples, &mut output);
+    resampler.flush(&mut output);
+    Ok(output)
+}
+
+/// One windowed-sinc filter per fractional offset between input samples, each normalized to
+/// unity gain so silence and DC levels are preserved exactly
+fn build_kernel(half: usize, phases: usize, cutoff: f64, beta: f64) -> Vec<f32> {
+    let taps = 2 * half;
+    let mut kernel = Vec::with_capacity(phases * taps);
+
+    for phase in 0..phases {
+        let fraction = phase as f64 / phases as f64;
+        let filter: Vec<f64> = (0..taps)
+            .map(|k| {
+                // Distance in input samples from the output position to this tap
+                let distance = k as f64 + 1.0 - half as f64 - fraction;
+                cutoff * sinc(cutoff * distance) * kaiser(distance / half as f64, beta)
+            })
+            .collect();
+        let gain: f64 = filter.iter().sum();
+        kernel.extend(filter.iter().map(|&h| (h / gain) as f32));
+    }
+
+    kernel
+}
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-9 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+/// Kaiser window at `t` in -1..=1
+fn kaiser(t: f64, beta: f64) -> f64 {
+    if t.abs() > 1.0 {
+        return 0.0;
+    }
+    bessel_i0(beta * (1.0 - t * t).sqrt()) / bessel_i0(beta)
+}
+
+/// Zeroth-order modified Bessel function of the first kind, by its power series
+fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let mut k = 1.0;
+    while term > sum * 1e-12 {
+        term *= (x / (2.0 * k)).powi(2);
+        sum += term;
+        k += 1.0;
+    }
+    sum
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
@@ -0,0 +1,81 @@
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Fixed-size single-producer/single-consumer queue of samples.
+///
+/// Samples are stored as `f32` bits in atomics, so neither side ever takes a lock and the
+/// producer (the realtime audio callback) never blocks or allocates. When the consumer falls
+/// behind, new samples are dropped and counted rather than overwriting unread ones.
+struct Shared {
+    slots: Box<[AtomicU32]>,
+    mask: usize,
+    /// Total samples read; only the consumer writes it
+    head: AtomicUsize,
+    /// Total samples written; only the producer writes it
+    tail: AtomicUsize,
+    dropped: AtomicUsize,
+}
+
+/// Writing half of a ring buffer created by `ring_buffer`
+pub(crate) struct Producer {
+    shared: Arc<Shared>,
+}
+
+/// Reading half of a ring buffer created by `ring_buffer`
+pub(crate) struct Consumer {
+    shared: Arc<Shared>,
+}
+
+/// Create a ring buffer holding at least `capacity` samples (rounded up to a power of two)
+pub(crate) fn ring_buffer(capacity: usize) -> (Producer, Consumer) {
+    let capacity = capacity.max(1).next_power_of_two();
+    let shared = Arc::new(Shared {
+        slots: (0..capacity).map(|_| AtomicU32::new(0)).collect(),
+        mask: capacity - 1,
+        head: AtomicUsize::new(0),
+        tail: AtomicUsize::new(0),
+        dropped: AtomicUsize::new(0),
+    });
+    (Producer { shared: Arc::clone(&shared) }, Consumer { shared })
+}
+
+impl Producer {
+    /// Append as many samples as fit and return how many were written
+    pub fn push_slice(&mut self, samples: &[f32]) -> usize {
+        let shared = &*self.shared;
+        let tail = shared.tail.load(Ordering::Relaxed);
+        let head = shared.head.load(Ordering::Acquire);
+        let free = shared.slots.len() - tail.wrapping_sub(head);
+        let count = samples.len().min(free);
+
+        for (i, &sample) in samples[..count].iter().enumerate() {
+            shared.slots[tail.wrapping_add(i) & shared.mask].store(sample.to_bits(), Ordering::Relaxed);
+        }
+        shared.tail.store(tail.wrapping_add(count), Ordering::Release);
+
+        if count < samples.len() {
+            shared.dropped.fetch_add(samples.len() - count, Ordering::Relaxed);
+        }
+        count
+    }
+}
+
+impl Consumer {
+    /// Move every available sample to the end of `out` and return how many were read
+    pub fn pop_into(&mut self, out: &mut Vec<f32>) -> usize {
+        let shared = &*self.shared;
+        let head = shared.head.load(Ordering::Relaxed);
+        let tail = shared.tail.load(Ordering::Acquire);
+        let count = tail.wrapping_sub(head);
+
+        out.reserve(count);
+        out.extend((0..count).map(|i| f32::from_bits(shared.slots[head.wrapping_add(i) & shared.mask].load(Ordering::Relaxed))));
+        shared.head.store(tail, Ordering::Release);
+        count
+    }
+
+    /// Samples the producer had to drop because the buffer was full
+    pub fn dropped(&self) -> usize {
+        self.shared.dropped.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,161 @@
+use crate::error::{Result, VoiceError};
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+
+/// Which waiting transcription runs first when a worker becomes free
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+    /// Someone is waiting for the result: recordings and direct transcription calls
+    Interactive,
+}
+
+impl std::fmt::Display for Priority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Priority::Low => "low",
+            Priority::Normal => "normal",
+            Priority::High => "high",
+            Priority::Interactive => "interactive",
+        })
+    }
+}
+
+impl FromStr for Priority {
+    type Err = VoiceError;
+
+    /// Priorities a job can be submitted with; `interactive` is reserved for direct calls
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "low" => Ok(Priority::Low),
+            "normal" => Ok(Priority::Normal),
+            "high" => Ok(Priority::High),
+            other => Err(VoiceError::InvalidOptions(format!(
+                "Unknown priority: {} (expected low, normal or high)", other
+            ))),
+        }
+    }
+}
+
+/// A semaphore that hands free permits to the highest priority waiter, oldest first
+#[derive(Clone)]
+pub(crate) struct PrioritySemaphore {
+    inner: Arc<Mutex<SemaphoreState>>,
+}
+
+struct SemaphoreState {
+    available: usize,
+    next_ticket: u64,
+    waiting: BinaryHeap<Waiter>,
+}
+
+struct Waiter {
+    priority: Priority,
+    ticket: u64,
+    wake: oneshot::Sender<SlotPermit>,
+}
+
+impl Ord for Waiter {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // BinaryHeap pops the greatest: highest priority, then lowest ticket
+        self.priority.cmp(&other.priority).then_with(|| other.ticket.cmp(&self.ticket))
+    }
+}
+
+impl PartialOrd for Waiter {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Waiter {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Waiter {}
+
+/// Held while a transcription runs; dropping it passes the slot on
+pub(crate) struct SlotPermit {
+    inner: Arc<Mutex<SemaphoreState>>,
+}
+
+impl Drop for SlotPermit {
+    fn drop(&mut self) {
+        release(&self.inner);
+    }
+}
+
+fn release(inner: &Arc<Mutex<SemaphoreState>>) {
+    loop {
+        let waiter = {
+            let mut state = inner.lock().unwrap();
+            match state.waiting.pop() {
+                Some(waiter) => waiter,
+                None => {
+                    state.available += 1;
+                    return;
+                }
+            }
+        };
+        match waiter.wake.send(SlotPermit { inner: Arc::clone(inner) }) {
+            Ok(()) => return,
+            // The waiter gave up; hand the slot to the next one without recursing through drop
+            Err(permit) => std::mem::forget(permit),
+        }
+    }
+}
+
+impl PrioritySemaphore {
+    pub fn new(permits: usize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(SemaphoreState {
+                available: permits,
+                next_ticket: 0,
+                waiting: BinaryHeap::new(),
+            })),
+        }
+    }
+
+    /// Wait for a permit. A caller that stops waiting loses its place in the queue.
+    pub async fn acquire(&self, priority: Priority) -> Result<SlotPermit> {
+        match self.try_acquire_or_wait(priority) {
+            Ok(permit) => Ok(permit),
+            Err(receiver) => receiver.await.map_err(|_| closed()),
+        }
+    }
+
+    /// Like `acquire`, for code running on a blocking thread
+    pub fn acquire_blocking(&self, priority: Priority) -> Result<SlotPermit> {
+        match self.try_acquire_or_wait(priority) {
+            Ok(permit) => Ok(permit),
+            Err(receiver) => receiver.blocking_recv().map_err(|_| closed()),
+        }
+    }
+
+    fn try_acquire_or_wait(&self, priority: Priority) -> std::result::Result<SlotPermit, oneshot::Receiver<SlotPermit>> {
+        let mut state = self.inner.lock().unwrap();
+        if state.available > 0 {
+            state.available -= 1;
+            return Ok(SlotPermit { inner: Arc::clone(&self.inner) });
+        }
+        let (wake, receiver) = oneshot::channel();
+        let ticket = state.next_ticket;
+        state.next_ticket += 1;
+        state.waiting.push(Waiter { priority, ticket, wake });
+        Err(receiver)
+    }
+}
+
+fn closed() -> VoiceError {
+    VoiceError::WhisperTranscription("Transcription workers unavailable".to_string())
+}
@@ -0,0 +1,131 @@
+use crate::config::*;
+use crate::transcription::{Segment, Transcription};
+use serde::Serialize;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::time::Instant;
+
+/// Incremental output of a streaming transcription
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum StreamEvent {
+    /// Text for the audio after the last committed point; it may change on the next update
+    Partial(Segment),
+    /// Committed text that will not change again
+    Final(Segment),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct StreamingOptions {
+    /// How often the uncommitted tail of the recording is transcribed again
+    pub interval_ms: u64,
+    /// Uncommitted audio longer than this is committed even in the middle of a sentence
+    pub window_ms: u64,
+}
+
+impl Default for StreamingOptions {
+    fn default() -> Self {
+        Self {
+            interval_ms: DEFAULT_STREAM_INTERVAL_MS,
+            window_ms: DEFAULT_STREAM_WINDOW_MS,
+        }
+    }
+}
+
+/// Sliding-window state for a streaming transcription.
+///
+/// Each update transcribes the audio recorded since the last committed point. Every segment
+/// except the last is followed by more speech, so it is committed as final and the window start
+/// moves past it; the last segment is reported as a partial until later audio confirms it.
+pub(crate) struct StreamState {
+    options: StreamingOptions,
+    events: UnboundedSender<StreamEvent>,
+    committed_samples: usize,
+    segments: Vec<Segment>,
+    language: Option<String>,
+    last_update: Instant,
+}
+
+impl StreamState {
+    pub fn new(options: StreamingOptions, events: UnboundedSender<StreamEvent>) -> Self {
+        Self {
+            options,
+            events,
+            committed_samples: 0,
+            segments: Vec::new(),
+            language: None,
+            last_update: Instant::now(),
+        }
+    }
+
+    /// Whether the next window should be transcribed now
+    pub fn is_due(&self) -> bool {
+        self.last_update.elapsed().as_millis() >= self.options.interval_ms as u128
+    }
+
+    /// Number of recorded samples already covered by final segments
+    pub fn committed_samples(&self) -> usize {
+        self.committed_samples
+    }
+
+    /// Leave the audio before `sample` out of the transcription, like the silence before the speaker
+    pub fn skip_to(&mut self, sample: usize) {
+        self.committed_samples = self.committed_samples.max(sample);
+    }
+
+    /// Apply the transcription of the audio that starts at `committed_samples`.
+    ///
+    /// `flush` commits everything, as when the recording has stopped.
+    pub fn apply(&mut self, window: &Transcription, window_samples: usize, sample_rate: u32, flush: bool) {
+        self.last_update = Instant::now();
+        if sample_rate == 0 {
+            return;
+        }
+
+        if self.language.is_none() {
+            self.language = window.language.clone();
+        }
+
+        let offset = self.committed_samples as f32 / sample_rate as f32;
+        let window_ms = (window_samples as u64 * 1000) / sample_rate as u64;
+        let commit_all = flush || window_ms >= self.options.window_ms;
+        let commit_count = if commit_all {
+            window.segments.len()
+        } else {
+            window.segments.len().saturating_sub(1)
+        };
+
+        for segment in &window.segments[..commit_count] {
+            if is_speech(segment) {
+                let segment = segment.shifted(offset);
+                let _ = self.events.send(StreamEvent::Final(segment.clone()));
+                self.segments.push(segment);
+            }
+        }
+
+        if commit_all {
+            self.committed_samples += window_samples;
+        } else {
+            if commit_count > 0 {
+                let end = window.segments[commit_count - 1].end;
+                let end_samples = (end * sample_rate as f32).round() as usize;
+                self.committed_samples += end_samples.min(window_samples);
+            }
+            if let Some(segment) = window.segments.last().filter(|s| is_speech(s)) {
+                let _ = self.events.send(StreamEvent::Partial(segment.shifted(offset)));
+            }
+        }
+    }
+
+    /// Combine every final segment into the result for the whole recording
+    pub fn finish(self, duration: f32) -> Transcription {
+        let mut transcription = Transcription::from_segments(self.segments, self.language, duration);
+        if transcription.text.is_empty() {
+            transcription.text = "No speech detected in audio".to_string();
+        }
+        transcription
+    }
+}
+
+fn is_speech(segment: &Segment) -> bool {
+    !segment.text.trim().is_empty() && segment.no_speech_prob < NO_SPEECH_THRESHOLD
+}
@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+
+/// A timed span of transcribed text
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Segment {
+    /// Start time in seconds from the beginning of the audio
+    pub start: f32,
+    /// End time in seconds from the beginning of the audio
+    pub end: f32,
+    pub text: String,
+    /// Probability (0.0-1.0) that the segment contains no speech at all
+    pub no_speech_prob: f32,
+    /// Per-word timings, only filled in when word timestamps were requested
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub words: Vec<Word>,
+}
+
+impl Segment {
+    /// The same segment moved `offset` seconds later, e.g. from a window onto the whole recording
+    pub(crate) fn shifted(&self, offset: f32) -> Segment {
+        let mut shifted = self.clone();
+        shifted.start += offset;
+        shifted.end += offset;
+        for word in &mut shifted.words {
+            word.start += offset;
+            word.end += offset;
+        }
+        shifted
+    }
+}
+
+/// A single word with its timing and Whisper's confidence in it
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Word {
+    pub word: String,
+    /// Start time in seconds from the beginning of the audio
+    pub start: f32,
+    /// End time in seconds from the beginning of the audio
+    pub end: f32,
+    /// Mean probability (0.0-1.0) of the tokens making up the word
+    pub probability: f32,
+}
+
+/// Timing and probability of one decoded text token
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct TokenTiming {
+    /// Raw token bytes; a multi-byte character may be split across tokens
+    pub bytes: Vec<u8>,
+    pub start: f32,
+    pub end: f32,
+    pub probability: f32,
+}
+
+/// Merge tokens into words: a token starting with a space begins a new word, anything
+/// else (word pieces, punctuation) is appended to the current one
+pub(crate) fn words_from_tokens(tokens: &[TokenTiming]) -> Vec<Word> {
+    fn finish(bytes: &[u8], start: f32, end: f32, probabilities: &[f32], words: &mut Vec<Word>) {
+        let word = String::from_utf8_lossy(bytes).trim().to_string();
+        if !word.is_empty() {
+            let probability = probabilities.iter().sum::<f32>() / probabilities.len() as f32;
+            words.push(Word { word, start, end, probability });
+        }
+    }
+
+    let mut words = Vec::new();
+    let mut bytes = Vec::new();
+    let mut probabilities = Vec::new();
+    let (mut start, mut end) = (0.0, 0.0);
+
+    for token in tokens {
+        if token.bytes.first() == Some(&b' ') && !bytes.is_empty() {
+            finish(&bytes, start, end, &probabilities, &mut words);
+            bytes.clear();
+            probabilities.clear();
+        }
+        if bytes.is_empty() {
+            start = token.start;
+        }
+        bytes.extend_from_slice(&token.bytes);
+        probabilities.push(token.probability);
+        end = token.end.max(start);
+    }
+    if !bytes.is_empty() {
+        finish(&bytes, start, end, &probabilities, &mut words);
+    }
+
+    words
+}
+
+/// Structured result of a transcription
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Transcription {
+    /// Full transcript (or a status message when no speech was recognised)
+    pub text: String,
+    pub segments: Vec<Segment>,
+    /// Language code detected by Whisper, e.g. "en"
+    pub language: Option<String>,
+    /// Duration of the transcribed audio in seconds
+    pub duration: f32,
+}
+
+impl Transcription {
+    /// A result that carries only a message, with no timed segments
+    pub fn from_text(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Build a result from segments, joining their text into the full transcript
+    pub fn from_segments(segments: Vec<Segment>, language: Option<String>, duration: f32) -> Self {
+        let text = segments.iter().map(|s| s.text.as_str()).collect::<String>().trim().to_string();
+        Self {
+            text,
+            segments,
+            language,
+            duration,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_else(|e| format!("{{\"error\": \"{}\"}}", e))
+    }
+}
+
+impl std::fmt::Display for Transcription {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.text)
+    }
+}
@@ -0,0 +1,281 @@
+use crate::config::*;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+/// How speech is told apart from background noise
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VadMode {
+    /// Energy relative to an adaptive noise-floor estimate, with onset/offset hysteresis
+    #[default]
+    Adaptive,
+    /// RMS level against a fixed threshold, as before the adaptive detector existed
+    Threshold,
+}
+
+impl FromStr for VadMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "adaptive" => Ok(VadMode::Adaptive),
+            "threshold" | "fixed" => Ok(VadMode::Threshold),
+            other => Err(format!("Unknown voice activity detector '{}' (expected adaptive or threshold)", other)),
+        }
+    }
+}
+
+impl std::fmt::Display for VadMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            VadMode::Adaptive => "adaptive",
+            VadMode::Threshold => "threshold",
+        })
+    }
+}
+
+/// Voice activity detection settings; `calibrate` recommends values for the room
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VadOptions {
+    pub mode: VadMode,
+    /// Background level in dBFS assumed until the detector has measured the room
+    pub initial_noise_floor_db: f32,
+    /// Audio at the start of a recording taken as background to set the noise floor; with 0
+    /// the floor starts at `initial_noise_floor_db`, as with a calibrated profile
+    pub seed_ms: u64,
+    /// Quietest level in dBFS that can count as speech however quiet the room is
+    pub min_speech_level_db: f32,
+    /// RMS level above which `VadMode::Threshold` hears speech
+    pub threshold: f32,
+    /// Level above the noise floor, in dB, at which speech starts
+    pub onset_db: f32,
+    /// Level above the noise floor, in dB, below which speech ends; lower than `onset_db`
+    /// so speech does not flicker on and off around a single threshold
+    pub offset_db: f32,
+    /// Loud sounds shorter than this (clicks, a door, a cough) are not speech
+    pub min_speech_ms: u64,
+    /// Speech continues through dips below the offset level shorter than this
+    pub hangover_ms: u64,
+}
+
+impl Default for VadOptions {
+    fn default() -> Self {
+        Self {
+            mode: VadMode::default(),
+            initial_noise_floor_db: VAD_INITIAL_NOISE_FLOOR_DB,
+            seed_ms: DEFAULT_VAD_SEED_MS,
+            min_speech_level_db: VAD_MIN_SPEECH_LEVEL_DB,
+            threshold: SILENCE_THRESHOLD,
+            onset_db: DEFAULT_VAD_ONSET_DB,
+            offset_db: DEFAULT_VAD_OFFSET_DB,
+            min_speech_ms: DEFAULT_VAD_MIN_SPEECH_MS,
+            hangover_ms: DEFAULT_VAD_HANGOVER_MS,
+        }
+    }
+}
+
+/// What the detector has heard so far
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VoiceActivity {
+    /// Nobody has started speaking yet
+    Waiting,
+    Speaking,
+    /// The speaker paused or finished
+    Silence,
+}
+
+/// Streaming voice activity detector working on short frames.
+///
+/// The noise floor starts at the quietest level of the first `seed_ms` of audio, so a room
+/// that is already noisy is not speech from the first sample. After that it follows the
+/// lowest smoothed frame level of the last `VAD_NOISE_WINDOW_MS`: it drops to a quieter level
+/// at once but rises by at most `VAD_NOISE_RISE_DB_PER_SECOND`. Speech has short gaps between
+/// words, so the minimum tracks the background even while someone talks, and a long stretch of
+/// speech without pauses is not taken for the background before it ends. A steady noise that
+/// starts later, such as an office air conditioner, becomes the floor after several seconds.
+pub struct VoiceActivityDetector {
+    options: VadOptions,
+    frame_len: usize,
+    /// Samples of the frame being filled
+    frame: Vec<f32>,
+    /// State of the DC-blocking filter applied before measuring levels
+    dc_input: f32,
+    dc_output: f32,
+    smoothed_db: Option<f32>,
+    recent_levels: VecDeque<f32>,
+    window_frames: usize,
+    seed_frames: usize,
+    /// Largest rise of the noise floor from one frame to the next
+    rise_per_frame_db: f32,
+    level_db: f32,
+    noise_floor_db: f32,
+    speaking: bool,
+    loud_frames: usize,
+    quiet_frames: usize,
+    min_speech_frames: usize,
+    hangover_frames: usize,
+    /// Complete frames processed so far
+    frames: usize,
+    /// Frame where the current run of loud frames began
+    loud_since: usize,
+    /// Sample index where the first confirmed speech began
+    onset: Option<usize>,
+    /// Sample index where the most recent run of speech began
+    last_onset: Option<usize>,
+}
+
+impl VoiceActivityDetector {
+    pub fn new(options: VadOptions, sample_rate: u32) -> Self {
+        let frames = |ms: u64| ms.div_ceil(VAD_FRAME_MS) as usize;
+        let window_frames = frames(VAD_NOISE_WINDOW_MS).max(1);
+
+        Self {
+            frame_len: samples_for_duration_ms(sample_rate, VAD_FRAME_MS).max(1),
+            frame: Vec::new(),
+            dc_input: 0.0,
+            dc_output: 0.0,
+            smoothed_db: None,
+            recent_levels: VecDeque::with_capacity(window_frames),
+            window_frames,
+            seed_frames: frames(options.seed_ms),
+            rise_per_frame_db: VAD_NOISE_RISE_DB_PER_SECOND * VAD_FRAME_MS as f32 / 1000.0,
+            level_db: VAD_MIN_LEVEL_DB,
+            noise_floor_db: options.initial_noise_floor_db,
+            speaking: false,
+            loud_frames: 0,
+            quiet_frames: 0,
+            min_speech_frames: frames(options.min_speech_ms).max(1),
+            hangover_frames: frames(options.hangover_ms),
+            frames: 0,
+            loud_since: 0,
+            onset: None,
+            last_onset: None,
+            options,
+        }
+    }
+
+    /// Feed newly captured samples and return whether speech is ongoing
+    pub fn process(&mut self, samples: &[f32]) -> bool {
+        for &sample in samples {
+            self.dc_output = sample - self.dc_input + 0.995 * self.dc_output;
+            self.dc_input = sample;
+            self.frame.push(self.dc_output);
+
+            if self.frame.len() == self.frame_len {
+                let mean_square = self.frame.iter().map(|x| x * x).sum::<f32>() / self.frame_len as f32;
+                self.frame.clear();
+                self.process_frame(mean_square);
+                self.frames += 1;
+            }
+        }
+        self.speaking
+    }
+
+    fn process_frame(&mut self, mean_square: f32) {
+        if self.options.mode == VadMode::Threshold {
+            let was_speaking = self.speaking;
+            self.speaking = mean_square.sqrt() > self.options.threshold;
+            if self.speaking && !was_speaking {
+                self.mark_onset(self.frames * self.frame_len);
+            }
+            return;
+        }
+
+        self.level_db = (10.0 * (mean_square + 1e-10).log10()).max(VAD_MIN_LEVEL_DB);
+        let smoothed = match self.smoothed_db {
+            Some(previous) => 0.7 * previous + 0.3 * self.level_db,
+            None => self.level_db,
+        };
+        self.smoothed_db = Some(smoothed);
+
+        if self.recent_levels.len() == self.window_frames {
+            self.recent_levels.pop_front();
+        }
+        self.recent_levels.push_back(smoothed);
+        let quietest = self.recent_levels.iter().copied().fold(f32::INFINITY, f32::min);
+
+        if self.frames < self.seed_frames {
+            // Whatever is heard first is the background
+            self.noise_floor_db = quietest;
+            return;
+        }
+        self.noise_floor_db = if quietest <= self.noise_floor_db {
+            quietest
+        } else {
+            (self.noise_floor_db + self.rise_per_frame_db).min(quietest)
+        };
+
+        let audible = self.level_db >= self.options.min_speech_level_db;
+        let snr = self.level_db - self.noise_floor_db;
+
+        if self.speaking {
+            if audible && snr >= self.options.offset_db {
+                self.quiet_frames = 0;
+            } else {
+                self.quiet_frames += 1;
+                if self.quiet_frames > self.hangover_frames {
+                    self.speaking = false;
+                    self.loud_frames = 0;
+                }
+            }
+        } else if audible && snr >= self.options.onset_db {
+            if self.loud_frames == 0 {
+                self.loud_since = self.frames;
+            }
+            self.loud_frames += 1;
+            if self.loud_frames >= self.min_speech_frames {
+                self.speaking = true;
+                self.quiet_frames = 0;
+                self.mark_onset(self.loud_since * self.frame_len);
+            }
+        } else {
+            self.loud_frames = 0;
+        }
+    }
+
+    fn mark_onset(&mut self, sample: usize) {
+        self.onset.get_or_insert(sample);
+        self.last_onset = Some(sample);
+    }
+
+    /// Whether speech is ongoing as of the last complete frame
+    pub fn is_speech(&self) -> bool {
+        self.speaking
+    }
+
+    pub fn activity(&self) -> VoiceActivity {
+        match (self.speaking, self.speech_detected()) {
+            (true, _) => VoiceActivity::Speaking,
+            (false, true) => VoiceActivity::Silence,
+            (false, false) => VoiceActivity::Waiting,
+        }
+    }
+
+    /// Whether any speech has been detected since the detector was created
+    pub fn speech_detected(&self) -> bool {
+        self.onset.is_some()
+    }
+
+    /// Index of the sample, counted from the first one processed, where the first speech began
+    pub fn speech_onset(&self) -> Option<usize> {
+        self.onset
+    }
+
+    /// Index of the sample where the most recent run of speech began
+    pub fn last_onset(&self) -> Option<usize> {
+        self.last_onset
+    }
+
+    /// Current background level estimate in dBFS
+    pub fn noise_floor_db(&self) -> f32 {
+        self.noise_floor_db
+    }
+
+    /// Level of the last complete frame in dBFS
+    pub fn level_db(&self) -> f32 {
+        self.level_db
+    }
+}
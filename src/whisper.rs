@@ -1,11 +1,172 @@
 use crate::config::*;
 use crate::error::{Result, VoiceError};
 use crate::platform::{debug_eprintln, load_whisper_context, create_whisper_state, run_whisper_transcription};
-use crate::audio::AudioProcessor;
-use whisper_rs::{FullParams, SamplingStrategy, WhisperContext};
+use crate::audio::{AudioBuffer, AudioProcessor};
+use crate::engine::TranscriptionEngine;
+use crate::resample::ResampleQuality;
+use crate::transcription::{words_from_tokens, Segment, TokenTiming, Transcription};
+use std::ffi::{c_int, c_void};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperState, WhisperSysContext, WhisperSysState};
+
+/// How Whisper searches for the most likely token sequence
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DecodingStrategy {
+    /// Take the most likely token; `best_of` candidates are sampled once the temperature is above zero
+    Greedy { best_of: u32 },
+    /// Keep `beam_size` hypotheses alive; `patience` below zero uses whisper.cpp's default
+    BeamSearch { beam_size: u32, patience: f32 },
+}
+
+impl Default for DecodingStrategy {
+    fn default() -> Self {
+        DecodingStrategy::Greedy { best_of: 1 }
+    }
+}
+
+/// Decoder settings, including the temperature fallback used when a decode looks like a failure.
+///
+/// The fallback runs inside whisper.cpp, one 30-second window at a time as in the reference
+/// Whisper implementation: only a window that fails is decoded again at the next temperature.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecodingOptions {
+    pub strategy: DecodingStrategy,
+    /// Initial sampling temperature (0.0 is deterministic)
+    pub temperature: f32,
+    /// Added to the temperature on each fallback retry, up to 1.0; 0.0 disables fallback
+    pub temperature_increment: f32,
+    /// Fall back when the entropy of a window's last tokens is below this value. This is
+    /// whisper.cpp's stand-in for the reference compression ratio check: repetition loops
+    /// have low entropy.
+    pub entropy_threshold: f32,
+    /// Fall back when a window's average token log-probability is below this value
+    pub logprob_threshold: f32,
+}
+
+impl Default for DecodingOptions {
+    /// The thresholds used by the reference Whisper implementation and whisper.cpp
+    fn default() -> Self {
+        Self {
+            strategy: DecodingStrategy::default(),
+            temperature: 0.0,
+            temperature_increment: 0.2,
+            entropy_threshold: 2.4,
+            logprob_threshold: -1.0,
+        }
+    }
+}
+
+impl DecodingOptions {
+    pub fn validate(&self) -> Result<()> {
+        match self.strategy {
+            DecodingStrategy::Greedy { best_of: 0 } => {
+                return Err(VoiceError::InvalidOptions("best_of must be at least 1".to_string()));
+            }
+            DecodingStrategy::BeamSearch { beam_size: 0, .. } => {
+                return Err(VoiceError::InvalidOptions("beam_size must be at least 1".to_string()));
+            }
+            _ => {}
+        }
+        if !(0.0..=1.0).contains(&self.temperature) {
+            return Err(VoiceError::InvalidOptions(format!("temperature must be between 0.0 and 1.0, got {}", self.temperature)));
+        }
+        if self.temperature_increment.is_nan() || self.temperature_increment < 0.0 {
+            return Err(VoiceError::InvalidOptions(format!("temperature_increment must not be negative, got {}", self.temperature_increment)));
+        }
+        if !self.entropy_threshold.is_finite() {
+            return Err(VoiceError::InvalidOptions(format!("entropy_threshold must be a finite number, got {}", self.entropy_threshold)));
+        }
+        if !self.logprob_threshold.is_finite() {
+            return Err(VoiceError::InvalidOptions(format!("logprob_threshold must be a finite number, got {}", self.logprob_threshold)));
+        }
+        Ok(())
+    }
+
+    fn sampling_strategy(&self) -> SamplingStrategy {
+        match self.strategy {
+            DecodingStrategy::Greedy { best_of } => SamplingStrategy::Greedy { best_of: best_of as i32 },
+            DecodingStrategy::BeamSearch { beam_size, patience } => SamplingStrategy::BeamSearch { beam_size: beam_size as i32, patience },
+        }
+    }
+}
+
+/// Options controlling what Whisper produces for a piece of audio
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TranscriptionOptions {
+    /// Spoken language as an ISO 639-1 code such as "fr" or "de"; `None` or "auto" detects it
+    pub language: Option<String>,
+    /// Translate the speech into English instead of transcribing it verbatim
+    pub translate: bool,
+    /// Text the decoder is primed with, useful for names, jargon and punctuation style
+    pub initial_prompt: Option<String>,
+    pub decoding: DecodingOptions,
+    /// Attach per-word start/end times and probabilities to each segment
+    pub word_timestamps: bool,
+    /// Filter quality used when converting the audio to 16kHz
+    pub resample_quality: ResampleQuality,
+}
+
+impl TranscriptionOptions {
+    /// The language to force, or `None` when Whisper should detect it
+    pub fn language(&self) -> Option<&str> {
+        self.language
+            .as_deref()
+            .map(str::trim)
+            .filter(|lang| !lang.is_empty() && !lang.eq_ignore_ascii_case("auto"))
+    }
+}
+
+/// Whisper states kept between transcriptions.
+///
+/// A state holds the decoder's KV cache and compute buffers, which are costly to allocate, so a
+/// transcription borrows an idle state and returns it when done. A new one is only created when
+/// every state is busy, and at most `capacity` (the number of transcription workers) are kept,
+/// so lowering the worker count frees the states that are no longer needed.
+pub(crate) struct StatePool<S = WhisperState> {
+    idle: Mutex<Vec<S>>,
+    capacity: AtomicUsize,
+}
+
+impl<S> StatePool<S> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            idle: Mutex::new(Vec::new()),
+            capacity: AtomicUsize::new(capacity),
+        }
+    }
+
+    /// An idle state, or a new one from `create` when all of them are busy
+    pub fn take(&self, create: impl FnOnce() -> Result<S>) -> Result<S> {
+        if let Some(state) = self.idle.lock().unwrap().pop() {
+            return Ok(state);
+        }
+        create()
+    }
+
+    /// Keep `state` for the next transcription, or free it when the pool is full
+    pub fn give_back(&self, state: S) {
+        let mut idle = self.idle.lock().unwrap();
+        if idle.len() < self.capacity.load(Ordering::Relaxed) {
+            idle.push(state);
+        }
+    }
+
+    pub fn set_capacity(&self, capacity: usize) {
+        self.capacity.store(capacity, Ordering::Relaxed);
+        self.idle.lock().unwrap().truncate(capacity);
+    }
+
+    #[cfg(test)]
+    pub fn idle_count(&self) -> usize {
+        self.idle.lock().unwrap().len()
+    }
+}
 
 pub struct WhisperTranscriber {
     context: Option<WhisperContext>,
+    states: StatePool,
     audio_processor: AudioProcessor,
     debug_enabled: bool,
 }
@@ -14,6 +175,7 @@ impl WhisperTranscriber {
     pub fn new(debug_enabled: bool) -> Self {
         Self {
             context: None,
+            states: StatePool::new(DEFAULT_TRANSCRIPTION_WORKERS),
             audio_processor: AudioProcessor::new(debug_enabled),
             debug_enabled,
         }
@@ -24,120 +186,243 @@ impl WhisperTranscriber {
         
         Ok(Self {
             context: Some(context),
+            states: StatePool::new(DEFAULT_TRANSCRIPTION_WORKERS),
             audio_processor: AudioProcessor::new(debug_enabled),
             debug_enabled,
         })
     }
 
-    pub fn has_model(&self) -> bool {
-        self.context.is_some()
-    }
-
-    pub async fn transcribe_audio(&self, audio_data: Vec<f32>) -> Result<String> {
-        if audio_data.is_empty() {
-            return Ok("No audio data recorded".to_string());
+    fn transcribe_with_whisper(
+        &self,
+        ctx: &WhisperContext,
+        audio_data: &[f32],
+        options: &TranscriptionOptions,
+        cancel: &CancellationToken,
+        progress: &mut dyn FnMut(f32),
+    ) -> Result<Transcription> {
+        let duration_seconds = audio_data.len() as f32 / WHISPER_SAMPLE_RATE as f32;
+        let language = Self::resolve_language(ctx, options)?;
+        options.decoding.validate()?;
+        
+        // States are reused by later transcriptions instead of being created for each one
+        let mut state = self.states.take(|| {
+            debug_eprintln!(self.debug_enabled, "🧠 Creating a Whisper state");
+            create_whisper_state(ctx, self.debug_enabled)
+        })?;
+        let result = self.decode(ctx, &mut state, audio_data, options, language.as_deref(), cancel, progress);
+        if cancel.is_cancelled() {
+            // Free the state of an aborted run instead of keeping it for the next one
+            return Err(VoiceError::Cancelled);
         }
-
-        if let Some(ref ctx) = self.context {
-            // Convert audio to the format Whisper expects (16kHz, mono)
-            let processed_audio = self.audio_processor.prepare_for_whisper(&audio_data)?;
-            
-            // Validate the processed audio
-            self.audio_processor.validate_audio(&processed_audio, WHISPER_SAMPLE_RATE)?;
-            
-            // Perform transcription
-            self.transcribe_with_whisper(ctx, &processed_audio).await
-        } else {
-            Err(VoiceError::WhisperModelNotLoaded)
+        let language = whisper_rs::get_lang_str(state.full_lang_id_from_state()).map(str::to_string);
+        self.states.give_back(state);
+        let segments = result?;
+        
+        let mut transcription = Transcription::from_segments(segments, language, duration_seconds);
+        
+        // Enhanced result analysis
+        if transcription.text.is_empty() {
+            transcription.text = "No speech detected in audio (Whisper returned empty result)".to_string();
+        } else if transcription.text.contains("[SOUND]") {
+            transcription.text = format!("Whisper detected audio but no clear speech: '{}' - try speaking louder/clearer or recording longer", transcription.text);
         }
+        
+        Ok(transcription)
     }
 
-    pub async fn transcribe_with_validation(&self, audio_data: Vec<f32>) -> Result<String> {
-        if audio_data.is_empty() {
-            return Ok("No audio data recorded".to_string());
+    /// Decode with `state`; whisper.cpp retries failed windows at higher temperatures itself
+    #[allow(clippy::too_many_arguments)]
+    fn decode(
+        &self,
+        ctx: &WhisperContext,
+        state: &mut WhisperState,
+        audio_data: &[f32],
+        options: &TranscriptionOptions,
+        language: Option<&str>,
+        cancel: &CancellationToken,
+        progress: &mut dyn FnMut(f32),
+    ) -> Result<Vec<Segment>> {
+        let mut params = self.build_params(options, language);
+        // whisper.cpp polls the abort callback between steps of the encoder and decoder, and
+        // reports progress every few percent of the audio.
+        // SAFETY: `cancel` and `progress` outlive the `full()` call below, which is the only user of `params`.
+        let mut progress: &mut dyn FnMut(f32) = &mut *progress;
+        unsafe {
+            params.set_abort_callback(Some(abort_when_cancelled));
+            params.set_abort_callback_user_data(cancel as *const CancellationToken as *mut c_void);
+            params.set_progress_callback(Some(report_progress));
+            params.set_progress_callback_user_data(&mut progress as *mut &mut dyn FnMut(f32) as *mut c_void);
         }
-
-        if let Some(ref ctx) = self.context {
-            // Convert audio to the format Whisper expects (16kHz, mono)
-            let processed_audio = self.audio_processor.prepare_for_whisper(&audio_data)?;
-            
-            // Perform transcription with enhanced result analysis
-            self.transcribe_with_whisper(ctx, &processed_audio).await
-        } else {
-            // Fallback to placeholder if no model loaded
-            Ok(format!("Transcribed {} audio samples (Whisper model not loaded - use new_with_model() to load a model)", audio_data.len()))
-        }
-    }
-
-    async fn transcribe_with_whisper(&self, ctx: &WhisperContext, audio_data: &[f32]) -> Result<String> {
-        // Audio validation and debugging
-        let duration_seconds = audio_data.len() as f32 / WHISPER_SAMPLE_RATE as f32;
-        let max_amplitude = audio_data.iter().map(|&x| x.abs()).max_by(|a, b| a.partial_cmp(b).unwrap()).unwrap_or(0.0);
-        let rms = (audio_data.iter().map(|&x| x * x).sum::<f32>() / audio_data.len() as f32).sqrt();
-        
-        debug_eprintln!(self.debug_enabled, "🎤 Audio stats: {:.2}s duration, max amplitude: {:.4}, RMS: {:.4}", 
-                duration_seconds, max_amplitude, rms);
         
-        // Check minimum requirements
-        if duration_seconds < MIN_AUDIO_DURATION {
-            return Err(VoiceError::AudioTooShort { duration: duration_seconds });
-        }
+        debug_eprintln!(self.debug_enabled, "🤖 Running Whisper transcription (language: {}, translate: {}, temperature: {:.1})...", 
+                language.unwrap_or("auto"), options.translate, options.decoding.temperature);
         
-        if max_amplitude < MIN_AUDIO_AMPLITUDE {
-            return Err(VoiceError::AudioTooQuiet { amplitude: max_amplitude });
+        let result = run_whisper_transcription(state, params, audio_data, self.debug_enabled);
+        if cancel.is_cancelled() {
+            return Err(VoiceError::Cancelled);
         }
+        result?;
         
-        // Create transcription parameters
-        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+        self.collect_segments(ctx, state, options)
+    }
+
+    fn build_params<'a>(&self, options: &'a TranscriptionOptions, language: Option<&'a str>) -> FullParams<'a, 'a> {
+        let decoding = &options.decoding;
+        let mut params = FullParams::new(decoding.sampling_strategy());
         
         // Improved Whisper settings for better speech detection
-        params.set_language(None); // Auto-detect language
+        params.set_language(language); // None auto-detects the language
+        params.set_translate(options.translate);
+        if let Some(prompt) = options.initial_prompt.as_deref().filter(|p| !p.trim().is_empty()) {
+            params.set_initial_prompt(prompt);
+        }
         params.set_print_special(false);
         params.set_print_progress(false);
         params.set_print_realtime(false);
         params.set_print_timestamps(false);
         params.set_suppress_blank(true);
         params.set_suppress_nst(true);
-        params.set_temperature(0.0);
         params.set_max_initial_ts(1.0);
         params.set_length_penalty(-1.0);
+        params.set_token_timestamps(options.word_timestamps);
         
-        debug_eprintln!(self.debug_enabled, "🤖 Running Whisper transcription...");
+        // whisper.cpp decodes a 30s window again at the next temperature when its entropy or
+        // average log-probability falls below these thresholds
+        params.set_temperature(decoding.temperature);
+        params.set_temperature_inc(decoding.temperature_increment);
+        params.set_entropy_thold(decoding.entropy_threshold);
+        params.set_logprob_thold(decoding.logprob_threshold);
         
-        // Create state and run the transcription
-        let mut state = create_whisper_state(ctx, self.debug_enabled)?;
-        run_whisper_transcription(&mut state, params, audio_data, self.debug_enabled)?;
-        
-        // Collect the transcribed text with detailed logging
-        let num_segments = state.full_n_segments()?;
+        params
+    }
+
+    /// Collect the timed segments from a finished decode
+    fn collect_segments(&self, ctx: &WhisperContext, state: &WhisperState, options: &TranscriptionOptions) -> Result<Vec<Segment>> {
+        let num_segments = state.full_n_segments();
         debug_eprintln!(self.debug_enabled, "📝 Whisper found {} segments", num_segments);
         
-        let mut result = String::new();
-        let mut all_segments = Vec::new();
+        let mut segments = Vec::with_capacity(num_segments.max(0) as usize);
+        let token_eot = ctx.token_eot();
         
-        for i in 0..num_segments {
-            let segment_text = state.full_get_segment_text(i)?;
-            let start_time = state.full_get_segment_t0(i)?;
-            let end_time = state.full_get_segment_t1(i)?;
+        for segment in state.as_iter() {
+            let text = segment.to_str_lossy()?.into_owned();
+            // Whisper timestamps are in centiseconds
+            let start = segment.start_timestamp() as f32 / 100.0;
+            let end = segment.end_timestamp() as f32 / 100.0;
+            let no_speech_prob = segment.no_speech_probability();
             
-            debug_eprintln!(self.debug_enabled, "   Segment {}: [{:.2}s-{:.2}s] '{}'", i, start_time as f32 / 100.0, end_time as f32 / 100.0, segment_text);
-            all_segments.push(segment_text.clone());
-            result.push_str(&segment_text);
+            // Only text tokens count; timestamps and other special tokens sort after end-of-text
+            let mut logprobs = Vec::new();
+            let mut tokens = Vec::new();
+            // `clamp` panics if its bounds are inverted
+            let latest = end.max(start);
+            for token in (0..segment.n_tokens()).filter_map(|i| segment.get_token(i)) {
+                let data = token.token_data();
+                if data.id >= token_eot {
+                    continue;
+                }
+                logprobs.push(data.plog);
+                if options.word_timestamps {
+                    tokens.push(TokenTiming {
+                        bytes: token.to_bytes()?.to_vec(),
+                        start: (data.t0 as f32 / 100.0).clamp(start, latest),
+                        end: (data.t1 as f32 / 100.0).clamp(start, latest),
+                        probability: data.p,
+                    });
+                }
+            }
+            let words = words_from_tokens(&tokens);
+            let avg_logprob = if logprobs.is_empty() { 0.0 } else { logprobs.iter().sum::<f32>() / logprobs.len() as f32 };
+            
+            debug_eprintln!(self.debug_enabled, "   Segment {}: [{:.2}s-{:.2}s] (no speech: {:.2}, avg logprob: {:.2}) '{}'", 
+                    segment.segment_index(), start, end, no_speech_prob, avg_logprob, text);
+            
+            segments.push(Segment { start, end, text, no_speech_prob, words });
         }
         
-        let trimmed_result = result.trim();
-        
-        // Enhanced result analysis
-        if trimmed_result.is_empty() {
-            Ok("No speech detected in audio (Whisper returned empty result)".to_string())
-        } else if trimmed_result == "[SOUND]" || trimmed_result.contains("[SOUND]") {
-            Ok(format!("Whisper detected audio but no clear speech: '{}' - try speaking louder/clearer or recording longer", trimmed_result))
-        } else {
-            Ok(trimmed_result.to_string())
+        Ok(segments)
+    }
+
+    /// Validate the requested language against Whisper's language table and the loaded model
+    fn resolve_language(ctx: &WhisperContext, options: &TranscriptionOptions) -> Result<Option<String>> {
+        let language = options.language().map(str::to_ascii_lowercase);
+
+        if let Some(ref lang) = language {
+            if !lang.chars().all(|c| c.is_ascii_alphabetic()) || whisper_rs::get_lang_id(lang).is_none() {
+                return Err(VoiceError::UnsupportedLanguage(lang.clone()));
+            }
+        }
+
+        // English-only models (*.en.bin) silently ignore other languages and the translate flag
+        let needs_multilingual = options.translate || language.as_deref().is_some_and(|lang| lang != "en");
+        if needs_multilingual && !ctx.is_multilingual() {
+            return Err(VoiceError::UnsupportedLanguage(format!(
+                "{} (the loaded model is English-only; use a multilingual model such as ggml-base.bin)",
+                language.as_deref().unwrap_or("translation")
+            )));
         }
+
+        Ok(language)
     }
 
     pub fn get_audio_processor(&self) -> &AudioProcessor {
         &self.audio_processor
     }
-}
\ No newline at end of file
+}
+
+impl TranscriptionEngine for WhisperTranscriber {
+    fn has_model(&self) -> bool {
+        self.context.is_some()
+    }
+
+    fn transcribe(&self, audio: &AudioBuffer, options: &TranscriptionOptions) -> Result<Transcription> {
+        self.transcribe_cancellable(audio, options, &CancellationToken::new())
+    }
+
+    fn transcribe_cancellable(&self, audio: &AudioBuffer, options: &TranscriptionOptions, cancel: &CancellationToken) -> Result<Transcription> {
+        self.transcribe_with_progress(audio, options, cancel, &mut |_| {})
+    }
+
+    fn transcribe_with_progress(
+        &self,
+        audio: &AudioBuffer,
+        options: &TranscriptionOptions,
+        cancel: &CancellationToken,
+        progress: &mut dyn FnMut(f32),
+    ) -> Result<Transcription> {
+        let ctx = self.context.as_ref().ok_or(VoiceError::WhisperModelNotLoaded)?;
+
+        // Convert audio to the format Whisper expects (16kHz, mono)
+        let processor = AudioProcessor::new(self.debug_enabled).with_resample_quality(options.resample_quality);
+        let processed_audio = processor.prepare_for_whisper(audio)?;
+
+        // Empty, too short or silent audio never reaches whisper.cpp
+        processor.validate_audio(&processed_audio)?;
+        if cancel.is_cancelled() {
+            return Err(VoiceError::Cancelled);
+        }
+
+        self.transcribe_with_whisper(ctx, &processed_audio.samples, options, cancel, progress)
+    }
+
+    fn set_workers(&self, workers: usize) {
+        self.states.set_capacity(workers);
+    }
+}
+
+/// whisper.cpp abort callback; `user_data` points to the `CancellationToken` of the running transcription.
+///
+/// whisper-rs's `set_abort_callback_safe` is not used because it passes its boxed closure to a
+/// trampoline expecting the closure type itself, and its progress counterpart leaks the closure.
+unsafe extern "C" fn abort_when_cancelled(user_data: *mut c_void) -> bool {
+    // SAFETY: set up in `decode`, where the token outlives the transcription
+    let cancel = unsafe { &*(user_data as *const CancellationToken) };
+    cancel.is_cancelled()
+}
+
+/// whisper.cpp progress callback; `user_data` points to the `&mut dyn FnMut(f32)` set up in `decode`
+unsafe extern "C" fn report_progress(_: *mut WhisperSysContext, _: *mut WhisperSysState, percent: c_int, user_data: *mut c_void) {
+    // SAFETY: set up in `decode`, where the callback outlives the transcription
+    let progress = unsafe { &mut *(user_data as *mut &mut dyn FnMut(f32)) };
+    progress(percent as f32);
+}
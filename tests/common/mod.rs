@@ -0,0 +1,18 @@
+//! Fixtures shared by the unit tests and the integration test suites
+
+use std::path::Path;
+
+/// Write interleaved samples in -1.0..=1.0 to a 16-bit WAV file
+pub fn write_wav(path: &Path, sample_rate: u32, channels: u16, samples: impl IntoIterator<Item = f32>) {
+    let spec = hound::WavSpec {
+        channels,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = hound::WavWriter::create(path, spec).unwrap();
+    for sample in samples {
+        writer.write_sample((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16).unwrap();
+    }
+    writer.finalize().unwrap();
+}
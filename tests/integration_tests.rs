@@ -1,22 +1,16 @@
-use voice_to_text_mcp::VoiceToTextService;
+use voice_to_text_mcp::{AudioBuffer, VoiceToTextService};
 use std::time::Duration;
 use tokio::time::sleep;
 
+mod common;
+
 // Helper function to check if audio device is available
 fn has_audio_device() -> bool {
-    use cpal::traits::{DeviceTrait, HostTrait};
-    
-    let host = cpal::default_host();
-    match host.default_input_device() {
-        Some(device) => {
-            // Try to get a supported config to verify the device works
-            match device.default_input_config() {
-                Ok(_) => true,
-                Err(_) => false,
-            }
-        },
-        None => false,
-    }
+    // A default device that reports a usable config; builds without microphone support have none
+    VoiceToTextService::new()
+        .list_input_devices()
+        .map(|devices| devices.iter().any(|d| d.is_default && d.default_sample_rate.is_some()))
+        .unwrap_or(false)
 }
 
 #[tokio::test]
@@ -50,7 +44,7 @@ async fn test_complete_recording_workflow() {
         // Should no longer be recording
         assert!(!service.is_recording());
         
-        let transcription = stop_result.unwrap();
+        let transcription = stop_result.unwrap().text;
         assert!(transcription.contains("Transcribed") || transcription == "No audio data recorded");
     }
 }
@@ -161,12 +155,16 @@ async fn test_hardware_acceleration_integration() {
     let start_time = Instant::now();
     // Create an audio processor to test the processing pipeline
     let audio_processor = voice_to_text_mcp::AudioProcessor::new(false);
-    let processed_result = audio_processor.prepare_for_whisper(&test_audio);
+    let processed_result = audio_processor.prepare_for_whisper(&AudioBuffer::new(test_audio.clone(), sample_rate));
     let processing_duration = start_time.elapsed();
     
     assert!(processed_result.is_ok(), "Audio processing should succeed");
     let processed_audio = processed_result.unwrap();
     
+    // Audio that is already at 16kHz should not change length
+    assert_eq!(processed_audio.len(), test_audio.len());
+    let processed_audio = processed_audio.samples;
+    
     println!("⚡ Audio processing took: {:?}", processing_duration);
     
     // Validate processed audio
@@ -176,11 +174,11 @@ async fn test_hardware_acceleration_integration() {
     
     // Test transcription performance (without actual model)
     let start_time = Instant::now();
-    let transcription_result = service.transcribe_audio(test_audio.clone()).await;
+    let transcription_result = service.transcribe_audio(AudioBuffer::new(test_audio.clone(), sample_rate)).await;
     let transcription_duration = start_time.elapsed();
     
     assert!(transcription_result.is_ok(), "Transcription should handle gracefully");
-    let transcription = transcription_result.unwrap();
+    let transcription = transcription_result.unwrap().text;
     
     println!("🎯 Transcription took: {:?}", transcription_duration);
     println!("📝 Transcription result: {}", transcription);
@@ -249,10 +247,10 @@ async fn test_acceleration_audio_quality_consistency() {
         println!("🔬 Testing audio quality case {}", i + 1);
         
         let audio_processor = voice_to_text_mcp::AudioProcessor::new(false);
-        let processed = audio_processor.prepare_for_whisper(test_audio);
+        let processed = audio_processor.prepare_for_whisper(&AudioBuffer::new(test_audio.clone(), 44100));
         assert!(processed.is_ok(), "Audio processing should succeed for case {}", i + 1);
         
-        let processed_audio = processed.unwrap();
+        let processed_audio = processed.unwrap().samples;
         
         // Quality checks
         assert!(!processed_audio.is_empty(), "Processed audio should not be empty");
@@ -268,4 +266,38 @@ async fn test_acceleration_audio_quality_consistency() {
         
         println!("✅ Case {} passed quality checks", i + 1);
     }
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_listening_to_replayed_file() {
+    use voice_to_text_mcp::{DebugConfig, ListenOptions, MockEngine, ReplaySource, TranscriptionOptions};
+
+    let wav_path = std::env::temp_dir().join(format!("replay_source_{}.wav", std::process::id()));
+    // 0.3s of silence before 1s of "speech", in stereo
+    let samples = (0..10400).flat_map(|i| {
+        let sample = if i < 2400 { 0.0 } else { (i as f32 * 0.1).sin() * 0.25 };
+        [sample, sample]
+    });
+    common::write_wav(&wav_path, 8000, 2, samples);
+
+    let source = ReplaySource::from_file(&wav_path.to_string_lossy()).unwrap();
+    std::fs::remove_file(&wav_path).ok();
+
+    // A replayed file drives the whole listen flow without any audio hardware
+    let engine = MockEngine::new().then_text("Hello from a file.");
+    let service = VoiceToTextService::new_with_engine(engine.clone(), DebugConfig::default());
+    service.set_audio_source(source).unwrap();
+
+    let listen = ListenOptions { timeout_ms: 5000, silence_timeout_ms: 300, ..Default::default() };
+    let started = std::time::Instant::now();
+    let transcription = service.start_listening_with_options(&listen, &TranscriptionOptions::default()).await.unwrap();
+
+    // The file ends after 1.3s, and the silence replayed after it stops the recording
+    assert!(started.elapsed() < Duration::from_millis(3000), "{:?}", started.elapsed());
+    assert_eq!(transcription.text, "Hello from a file.");
+    assert!(!service.is_recording());
+    let calls = engine.calls();
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].sample_rate, 8000);
+    assert!(calls[0].sample_count >= 8000, "{} samples", calls[0].sample_count);
+}
@@ -1,13 +1,18 @@
 use voice_to_text_mcp::{VoiceToTextService, mcp_server::VoiceToTextMcpServer};
-use voice_to_text_mcp::mcp_server::{ListenRequest, TranscribeFileRequest};
+use voice_to_text_mcp::mcp_server::{CalibrateRequest, CancelJobRequest, GetJobRequest, ListenRequest, StopRecordingRequest, SubmitTranscriptionRequest, TranscribeFileRequest};
+use voice_to_text_mcp::{DecodingOptions, DecodingStrategy, TranscriptionOptions};
+use voice_to_text_mcp::{DebugConfig, MockEngine, SignalSource, VoiceError};
 use rmcp::handler::server::{ServerHandler, tool::Parameters};
 
+mod common;
+
 // Helper function to create ListenRequest with default values
 fn create_listen_request() -> ListenRequest {
     ListenRequest {
         timeout_ms: None,
         silence_timeout_ms: None,
         auto_stop: None,
+        ..Default::default()
     }
 }
 
@@ -59,6 +64,7 @@ async fn test_listen_with_custom_timeout() {
         timeout_ms: Some(1000),
         silence_timeout_ms: Some(500),
         auto_stop: Some(true),
+        ..Default::default()
     };
     let result = server.listen(Parameters(request)).await;
     
@@ -66,6 +72,56 @@ async fn test_listen_with_custom_timeout() {
     assert!(result.contains("Error: Whisper model not loaded"));
 }
 
+#[tokio::test]
+async fn test_listen_streaming_without_model() {
+    let service = VoiceToTextService::new();
+    let server = VoiceToTextMcpServer::new(service);
+    
+    let request = ListenRequest {
+        timeout_ms: Some(1000),
+        stream: Some(true),
+        stream_interval_ms: Some(250),
+        ..Default::default()
+    };
+    let result = server.listen(Parameters(request)).await;
+    
+    // Streaming needs a model just like a regular recording
+    assert!(result.contains("Error: Whisper model not loaded"));
+}
+
+#[tokio::test]
+async fn test_push_to_talk_tools_without_recording() {
+    let service = VoiceToTextService::new();
+    let server = VoiceToTextMcpServer::new(service);
+    
+    // Starting requires a model, like listen
+    let result = server.start_recording().await;
+    assert!(result.contains("Error: Whisper model not loaded"));
+    
+    // Nothing to stop or cancel
+    let result = server.stop_recording(Parameters(StopRecordingRequest::default())).await;
+    assert!(result.contains("Error: Not currently recording"));
+    let result = server.cancel_recording().await;
+    assert!(result.contains("Error: Not currently recording"));
+    
+    let status: serde_json::Value = serde_json::from_str(&server.recording_status().await).unwrap();
+    assert_eq!(status["recording"], false);
+    assert_eq!(status["duration_seconds"], 0.0);
+}
+
+#[tokio::test]
+async fn test_list_input_devices_tool() {
+    let service = VoiceToTextService::new();
+    let server = VoiceToTextMcpServer::new(service);
+    
+    // Either a JSON array of devices or an error when there is no audio system
+    let result = server.list_input_devices().await;
+    if !result.starts_with("Error") {
+        let devices: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert!(devices.is_array());
+    }
+}
+
 #[tokio::test]
 async fn test_listen_with_auto_stop_disabled() {
     let service = VoiceToTextService::new();
@@ -75,6 +131,7 @@ async fn test_listen_with_auto_stop_disabled() {
         timeout_ms: Some(1000),
         silence_timeout_ms: Some(500),
         auto_stop: Some(false),
+        ..Default::default()
     };
     let result = server.listen(Parameters(request)).await;
     
@@ -89,6 +146,7 @@ async fn test_transcribe_file_nonexistent() {
     
     let request = TranscribeFileRequest {
         file_path: "nonexistent.wav".to_string(),
+        ..Default::default()
     };
     let result = server.transcribe_file(Parameters(request)).await;
     
@@ -103,6 +161,7 @@ async fn test_transcribe_file_invalid_path() {
     
     let request = TranscribeFileRequest {
         file_path: "/invalid/path/file.wav".to_string(),
+        ..Default::default()
     };
     let result = server.transcribe_file(Parameters(request)).await;
     
@@ -110,6 +169,67 @@ async fn test_transcribe_file_invalid_path() {
     assert!(result.contains("Error"));
 }
 
+#[tokio::test]
+async fn test_transcribe_file_returns_structured_json() {
+    let service = VoiceToTextService::new();
+    let server = VoiceToTextMcpServer::new(service);
+    
+    let wav_path = std::env::temp_dir().join(format!("mcp_structured_{}.wav", std::process::id()));
+    common::write_wav(&wav_path, 16000, 1, (0..16000).map(|i| (i as f32 * 0.05).sin() * 0.25));
+    
+    let request = TranscribeFileRequest {
+        file_path: wav_path.to_string_lossy().to_string(),
+        ..Default::default()
+    };
+    let result = server.transcribe_file(Parameters(request)).await;
+    std::fs::remove_file(&wav_path).ok();
+    
+    // Without a model the placeholder transcript is still returned as structured JSON
+    let json: serde_json::Value = serde_json::from_str(&result).expect("transcribe_file should return JSON");
+    assert!(json["text"].as_str().unwrap().contains("model not loaded"));
+    assert!(json["segments"].is_array());
+}
+
+#[tokio::test]
+async fn test_transcribe_file_output_formats() {
+    let service = VoiceToTextService::new();
+    let server = VoiceToTextMcpServer::new(service);
+    
+    let wav_path = std::env::temp_dir().join(format!("mcp_formats_{}.wav", std::process::id()));
+    common::write_wav(&wav_path, 16000, 1, (0..16000).map(|i| (i as f32 * 0.05).sin() * 0.25));
+    let file_path = wav_path.to_string_lossy().to_string();
+    
+    // Unknown formats are rejected before any transcription work
+    let result = server.transcribe_file(Parameters(TranscribeFileRequest {
+        file_path: file_path.clone(),
+        format: Some("docx".to_string()),
+        write_sidecar: None,
+        ..Default::default()
+    })).await;
+    assert!(result.contains("Error: Unknown output format"));
+    
+    let result = server.transcribe_file(Parameters(TranscribeFileRequest {
+        file_path: file_path.clone(),
+        format: Some("vtt".to_string()),
+        write_sidecar: None,
+        ..Default::default()
+    })).await;
+    assert!(result.starts_with("WEBVTT"));
+    
+    let result = server.transcribe_file(Parameters(TranscribeFileRequest {
+        file_path: file_path.clone(),
+        format: Some("srt".to_string()),
+        write_sidecar: Some(true),
+        ..Default::default()
+    })).await;
+    let sidecar = wav_path.with_extension("srt");
+    assert!(result.contains("Saved srt transcript"));
+    assert!(sidecar.exists());
+    
+    std::fs::remove_file(&sidecar).ok();
+    std::fs::remove_file(&wav_path).ok();
+}
+
 #[tokio::test]
 async fn test_server_clone() {
     let service = VoiceToTextService::new();
@@ -138,12 +258,15 @@ async fn test_concurrent_requests() {
     // Test multiple concurrent transcribe requests
     let request1 = TranscribeFileRequest {
         file_path: "test1.wav".to_string(),
+        ..Default::default()
     };
     let request2 = TranscribeFileRequest {
         file_path: "test2.wav".to_string(),
+        ..Default::default()
     };
     let request3 = TranscribeFileRequest {
         file_path: "test3.wav".to_string(),
+        ..Default::default()
     };
     
     let server1 = server.clone();
@@ -172,6 +295,7 @@ async fn test_listen_parameter_defaults() {
         timeout_ms: None,
         silence_timeout_ms: None, 
         auto_stop: None,
+        ..Default::default()
     };
     let result = server.listen(Parameters(request)).await;
     
@@ -201,6 +325,7 @@ fn test_parameter_types() {
         timeout_ms: Some(5000),
         silence_timeout_ms: Some(1000),
         auto_stop: Some(true),
+        ..Default::default()
     };
     assert_eq!(listen_req.timeout_ms, Some(5000));
     assert_eq!(listen_req.silence_timeout_ms, Some(1000));
@@ -208,6 +333,188 @@ fn test_parameter_types() {
     
     let transcribe_req = TranscribeFileRequest {
         file_path: "test.wav".to_string(),
+        ..Default::default()
     };
     assert_eq!(transcribe_req.file_path, "test.wav");
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_transcription_params_from_tool_arguments() {
+    let request: ListenRequest = serde_json::from_value(serde_json::json!({
+        "timeout_ms": 5000,
+        "language": "fr",
+        "translate": true,
+        "initial_prompt": "Réunion budgétaire"
+    })).unwrap();
+    assert_eq!(request.timeout_ms, Some(5000));
+
+    let options = request.transcription.to_options(&TranscriptionOptions::default());
+    assert_eq!(options.language.as_deref(), Some("fr"));
+    assert!(options.translate);
+    assert_eq!(options.initial_prompt.as_deref(), Some("Réunion budgétaire"));
+
+    // Missing request values fall back to the server defaults
+    let defaults = TranscriptionOptions { language: Some("de".to_string()), ..Default::default() };
+    let request: TranscribeFileRequest = serde_json::from_value(serde_json::json!({
+        "file_path": "memo.wav"
+    })).unwrap();
+    assert_eq!(request.transcription.to_options(&defaults), defaults);
+}
+
+#[test]
+fn test_decoding_params_from_tool_arguments() {
+    let request: TranscribeFileRequest = serde_json::from_value(serde_json::json!({
+        "file_path": "memo.wav",
+        "beam_size": 5,
+        "temperature_increment": 0.0,
+        "entropy_threshold": 2.0
+    })).unwrap();
+
+    let options = request.transcription.to_options(&TranscriptionOptions::default());
+    assert_eq!(options.decoding.strategy, DecodingStrategy::BeamSearch { beam_size: 5, patience: -1.0 });
+    assert_eq!(options.decoding.temperature_increment, 0.0);
+    assert_eq!(options.decoding.entropy_threshold, 2.0);
+    assert_eq!(options.decoding.logprob_threshold, DecodingOptions::default().logprob_threshold);
+    assert!(!options.word_timestamps);
+
+    let request: TranscribeFileRequest = serde_json::from_value(serde_json::json!({
+        "file_path": "memo.wav",
+        "word_timestamps": true
+    })).unwrap();
+    assert!(request.transcription.to_options(&TranscriptionOptions::default()).word_timestamps);
+}
+
+#[tokio::test]
+async fn test_tools_with_mock_engine() {
+    let engine = MockEngine::new()
+        .then_text("Schedule the review for Friday.")
+        .then_error(VoiceError::WhisperTranscription("decoder failed".to_string()));
+    let service = VoiceToTextService::new_with_engine(engine.clone(), DebugConfig::default());
+    service.set_audio_source(SignalSource::new(16000).silence(300).tone(220.0, 0.5, 500)).unwrap();
+    let server = VoiceToTextMcpServer::new(service);
+
+    // The generated tone ends after 0.8s, so silence detection stops the recording
+    let request: ListenRequest = serde_json::from_value(serde_json::json!({
+        "timeout_ms": 5000,
+        "silence_timeout_ms": 300,
+        "language": "en"
+    })).unwrap();
+    let result = server.listen(Parameters(request)).await;
+    let json: serde_json::Value = serde_json::from_str(&result).expect("listen should return JSON");
+    assert_eq!(json["text"], "Schedule the review for Friday.");
+    assert_eq!(json["language"], "en");
+
+    // Engine errors are reported as tool errors
+    let result = server.start_recording().await;
+    assert!(!result.starts_with("Error"));
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    let result = server.stop_recording(Parameters(StopRecordingRequest::default())).await;
+    assert!(result.contains("Error: Whisper transcription failed: decoder failed"));
+
+    let calls = engine.calls();
+    assert_eq!(calls.len(), 2);
+    assert_eq!(calls[0].sample_rate, 16000);
+    assert!(calls[0].sample_count >= 8000);
+}
+
+#[tokio::test]
+async fn test_calibrate_tool() {
+    let service = VoiceToTextService::new();
+    service.set_audio_source(SignalSource::new(16000).noise(0.01, 2000)).unwrap();
+    let server = VoiceToTextMcpServer::new(service);
+
+    let request: CalibrateRequest = serde_json::from_value(serde_json::json!({
+        "duration_ms": 600,
+        "save": false
+    })).unwrap();
+    let result = server.calibrate(Parameters(request)).await;
+    let json: serde_json::Value = serde_json::from_str(&result).expect("calibrate should return JSON");
+
+    let noise_floor = json["noise_floor_db"].as_f64().unwrap();
+    assert!(noise_floor < -40.0 && noise_floor > -50.0, "noise floor {}", noise_floor);
+    assert_eq!(json["recommended"]["mode"], "adaptive");
+    assert_eq!(json["recommended"]["initial_noise_floor_db"], json["noise_floor_db"]);
+    assert!(json["profile_path"].is_null());
+}
+
+#[tokio::test]
+async fn test_continuous_listen_tool() {
+    let engine = MockEngine::new().then_text("Turn on the lights.");
+    let service = VoiceToTextService::new_with_engine(engine, DebugConfig::default());
+    service.set_audio_source(SignalSource::new(16000).silence(300).tone(220.0, 0.5, 500)).unwrap();
+    let server = VoiceToTextMcpServer::new(service);
+
+    let listener = server.clone();
+    let listen = tokio::spawn(async move {
+        let request: ListenRequest = serde_json::from_value(serde_json::json!({
+            "continuous": true,
+            "silence_timeout_ms": 300
+        })).unwrap();
+        listener.listen(Parameters(request)).await
+    });
+
+    // stop_recording ends the session; listen then returns every utterance
+    tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+    let result = server.stop_recording(Parameters(StopRecordingRequest::default())).await;
+    assert_eq!(result, "Continuous listening stopped");
+
+    let result = listen.await.unwrap();
+    let json: serde_json::Value = serde_json::from_str(&result).expect("listen should return JSON");
+    let utterances = json.as_array().unwrap();
+    assert_eq!(utterances.len(), 1);
+    assert_eq!(utterances[0]["index"], 0);
+    assert_eq!(utterances[0]["text"], "Turn on the lights.");
+}
+
+#[tokio::test]
+async fn test_job_tools() {
+    let engine = MockEngine::new().then_text("Chapter one.");
+    let service = VoiceToTextService::new_with_engine(engine, DebugConfig::default());
+    let server = VoiceToTextMcpServer::new(service);
+
+    let wav_path = std::env::temp_dir().join(format!("mcp_job_{}.wav", std::process::id()));
+    common::write_wav(&wav_path, 16000, 1, (0..16000).map(|i| (i as f32 * 0.05).sin() * 0.25));
+
+    let request: SubmitTranscriptionRequest = serde_json::from_value(serde_json::json!({
+        "file_path": wav_path.to_string_lossy(),
+        "priority": "high"
+    })).unwrap();
+    let result = server.submit_transcription(Parameters(request)).await;
+    let json: serde_json::Value = serde_json::from_str(&result).expect("submit_transcription should return JSON");
+    let job_id = json["id"].as_u64().unwrap();
+    assert_eq!(json["priority"], "high");
+
+    // Poll until the job is done
+    let mut state = String::new();
+    for _ in 0..50 {
+        let result = server.get_job(Parameters(GetJobRequest { job_id, format: None })).await;
+        let json: serde_json::Value = serde_json::from_str(&result).expect("get_job should return JSON");
+        state = json["state"].as_str().unwrap().to_string();
+        if state == "completed" {
+            assert_eq!(json["result"]["text"], "Chapter one.");
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    std::fs::remove_file(&wav_path).ok();
+    assert_eq!(state, "completed");
+
+    let result = server.get_job(Parameters(GetJobRequest { job_id, format: Some("text".to_string()) })).await;
+    assert_eq!(result.trim(), "Chapter one.");
+
+    let json: serde_json::Value = serde_json::from_str(&server.list_jobs().await).unwrap();
+    assert_eq!(json.as_array().unwrap().len(), 1);
+    assert_eq!(json[0]["state"], "completed");
+
+    // Finished jobs cannot be cancelled any more; unknown jobs and priorities are errors
+    let result = server.cancel_job(Parameters(CancelJobRequest { job_id })).await;
+    assert!(result.contains("\"completed\""));
+    let result = server.cancel_job(Parameters(CancelJobRequest { job_id: 42 })).await;
+    assert_eq!(result, "Error: Job not found: 42");
+    let request = SubmitTranscriptionRequest {
+        file_path: "talk.wav".to_string(),
+        priority: Some("urgent".to_string()),
+        ..Default::default()
+    };
+    assert!(server.submit_transcription(Parameters(request)).await.starts_with("Error: Invalid transcription options: Unknown priority"));
+}
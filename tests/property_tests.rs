@@ -1,4 +1,4 @@
-use voice_to_text_mcp::VoiceToTextService;
+use voice_to_text_mcp::{AudioBuffer, VoiceToTextService};
 use proptest::prelude::*;
 
 proptest! {
@@ -10,12 +10,12 @@ proptest! {
         let service = VoiceToTextService::new();
         
         rt.block_on(async {
-            let result = service.transcribe_audio(audio_samples.clone()).await;
+            let result = service.transcribe_audio(AudioBuffer::new(audio_samples.clone(), 44100)).await;
             
             // Property: transcription should always succeed
             assert!(result.is_ok());
             
-            let transcription = result.unwrap();
+            let transcription = result.unwrap().text;
             
             if audio_samples.is_empty() {
                 // Property: empty audio should return specific message
@@ -41,12 +41,12 @@ proptest! {
         
         rt.block_on(async {
             let audio_data = vec![0.5f32; audio_size];
-            let result = service.transcribe_audio(audio_data).await;
+            let result = service.transcribe_audio(AudioBuffer::new(audio_data, 44100)).await;
             
             // Property: transcription should always succeed
             assert!(result.is_ok());
             
-            let transcription = result.unwrap();
+            let transcription = result.unwrap().text;
             
             if audio_size == 0 {
                 // Property: empty audio should return specific message